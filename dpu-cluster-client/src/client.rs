@@ -0,0 +1,96 @@
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::program::Program;
+use crate::protocol;
+use crate::protocol::DaemonError;
+use crate::protocol::Request;
+use crate::protocol::Response;
+use crate::protocol::PROTOCOL_VERSION;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    Daemon(DaemonError),
+    UnexpectedResponse(Response),
+    Disconnected
+}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        ClientError::Io(err)
+    }
+}
+
+// The DPUs leased through a client are released by the daemon when the client disconnects.
+pub struct Client {
+    stream: UnixStream
+}
+
+impl Client {
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Self, ClientError> {
+        let stream = UnixStream::connect(path)?;
+        let mut client = Client { stream };
+
+        match client.request(&Request::Hello { version: PROTOCOL_VERSION })? {
+            Response::Hello(_) => Ok(client),
+            response => Err(ClientError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn lease(&mut self, nr_of_dpus: u32) -> Result<Vec<DpuId>, ClientError> {
+        match self.request(&Request::Lease(nr_of_dpus))? {
+            Response::Leased(dpus) => Ok(dpus),
+            response => Err(ClientError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn release(&mut self, dpus: &[DpuId]) -> Result<(), ClientError> {
+        self.request_done(&Request::Release(dpus.to_vec()))
+    }
+
+    pub fn load(&mut self, dpus: &[DpuId], program: &Program) -> Result<(), ClientError> {
+        self.request_done(&Request::Load(dpus.to_vec(), program.iram_sections().clone(), program.wram_sections().clone(), program.mram_sections().clone()))
+    }
+
+    pub fn boot(&mut self, dpus: &[DpuId]) -> Result<(), ClientError> {
+        self.request_done(&Request::Boot(dpus.to_vec()))
+    }
+
+    pub fn poll(&mut self, dpus: &[DpuId]) -> Result<RunStatus, ClientError> {
+        match self.request(&Request::Poll(dpus.to_vec()))? {
+            Response::Status(status) => Ok(status),
+            response => Err(ClientError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn copy_to(&mut self, dpu: DpuId, offset: u32, data: &[u8]) -> Result<(), ClientError> {
+        self.request_done(&Request::CopyTo { dpu, offset, data: data.to_vec() })
+    }
+
+    pub fn copy_from(&mut self, dpu: DpuId, offset: u32, length: u32) -> Result<Vec<u8>, ClientError> {
+        match self.request(&Request::CopyFrom { dpu, offset, length })? {
+            Response::Data(data) => Ok(data),
+            response => Err(ClientError::UnexpectedResponse(response)),
+        }
+    }
+
+    fn request_done(&mut self, request: &Request) -> Result<(), ClientError> {
+        match self.request(request)? {
+            Response::Done => Ok(()),
+            response => Err(ClientError::UnexpectedResponse(response)),
+        }
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response, ClientError> {
+        protocol::send(&mut self.stream, request)?;
+
+        match protocol::receive(&mut self.stream)? {
+            Some(Response::Error(err)) => Err(ClientError::Daemon(err)),
+            Some(response) => Ok(response),
+            None => Err(ClientError::Disconnected),
+        }
+    }
+}
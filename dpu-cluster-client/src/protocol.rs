@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::error::ClusterError;
+
+// Every message is framed by its length (u32, little endian). The first exchange is a handshake
+// where the client announces its protocol version.
+pub const PROTOCOL_VERSION: u16 = 2;
+pub const DEFAULT_SOCKET_PATH: &str = "/var/run/dpu-clusterd.sock";
+
+// large enough for a whole MRAM image (64 MiB) and the sections sent along with it
+const MAX_MESSAGE_SIZE: u32 = 1 << 27;
+
+pub type IramSections = HashMap<u16, Vec<u64>>;
+pub type WramSections = HashMap<u32, Vec<u32>>;
+pub type MramSections = HashMap<u32, Vec<u8>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    Hello { version: u16 },
+    Lease(u32),
+    Release(Vec<DpuId>),
+    Load(Vec<DpuId>, IramSections, WramSections, MramSections),
+    Boot(Vec<DpuId>),
+    Poll(Vec<DpuId>),
+    CopyTo { dpu: DpuId, offset: u32, data: Vec<u8> },
+    CopyFrom { dpu: DpuId, offset: u32, length: u32 }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonError {
+    UnsupportedVersion(u16),
+    InvalidRequest,
+    NotOwner(DpuId),
+    NotEnoughResources { expected: u32, found: u32 },
+    DpuIsAlreadyRunning,
+    ClusterFailure(String)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Hello(u16),
+    Done,
+    Leased(Vec<DpuId>),
+    Status(RunStatus),
+    Data(Vec<u8>),
+    Error(DaemonError)
+}
+
+pub fn send<W: Write, M: Message>(writer: &mut W, message: &M) -> io::Result<()> {
+    let mut encoder = Encoder::default();
+    message.encode(&mut encoder);
+
+    writer.write_all(&(encoder.0.len() as u32).to_le_bytes())?;
+    writer.write_all(&encoder.0)?;
+    writer.flush()
+}
+
+// returns None when the peer closed the connection
+pub fn receive<R: Read, M: Message>(reader: &mut R) -> io::Result<Option<M>> {
+    let mut length = [0u8; 4];
+
+    match reader.read_exact(&mut length) {
+        Ok(()) => (),
+        Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    let length = u32::from_le_bytes(length);
+    if length > MAX_MESSAGE_SIZE {
+        return Err(invalid_data());
+    }
+
+    // the buffer grows with the bytes actually received instead of trusting the announced length
+    let mut buffer = Vec::default();
+    reader.take(length as u64).read_to_end(&mut buffer)?;
+    if buffer.len() != length as usize {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated message"));
+    }
+
+    let mut decoder = Decoder(&buffer);
+    let message = M::decode(&mut decoder).ok_or_else(invalid_data)?;
+
+    if decoder.0.is_empty() {
+        Ok(Some(message))
+    } else {
+        Err(invalid_data())
+    }
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed message")
+}
+
+pub trait Message: Sized {
+    fn encode(&self, encoder: &mut Encoder);
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self>;
+}
+
+#[derive(Default)]
+pub struct Encoder(Vec<u8>);
+
+pub struct Decoder<'a>(&'a [u8]);
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.bytes(value.as_bytes());
+    }
+
+    fn dpu(&mut self, dpu: &DpuId) {
+        let (rank, slice, member) = dpu.members();
+        self.u8(rank);
+        self.u8(slice);
+        self.u8(member);
+    }
+
+    fn dpus(&mut self, dpus: &[DpuId]) {
+        self.u32(dpus.len() as u32);
+        for dpu in dpus {
+            self.dpu(dpu);
+        }
+    }
+
+    fn iram_sections(&mut self, sections: &IramSections) {
+        self.u32(sections.len() as u32);
+        for (offset, instructions) in sections {
+            self.u16(*offset);
+            self.u32(instructions.len() as u32);
+            for instruction in instructions {
+                self.u64(*instruction);
+            }
+        }
+    }
+
+    fn wram_sections(&mut self, sections: &WramSections) {
+        self.u32(sections.len() as u32);
+        for (offset, words) in sections {
+            self.u32(*offset);
+            self.u32(words.len() as u32);
+            for word in words {
+                self.u32(*word);
+            }
+        }
+    }
+
+    fn mram_sections(&mut self, sections: &MramSections) {
+        self.u32(sections.len() as u32);
+        for (offset, data) in sections {
+            self.u32(*offset);
+            self.bytes(data);
+        }
+    }
+}
+
+impl <'a> Decoder<'a> {
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.0.len() < length {
+            return None;
+        }
+
+        let (head, tail) = self.0.split_at(length);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).and_then(|bytes| bytes.try_into().ok()).map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).and_then(|bytes| bytes.try_into().ok()).map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes)
+    }
+
+    fn bytes(&mut self) -> Option<Vec<u8>> {
+        let length = self.u32()? as usize;
+        self.take(length).map(|bytes| bytes.to_vec())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?).ok()
+    }
+
+    fn dpu(&mut self) -> Option<DpuId> {
+        Some(DpuId::new(self.u8()?, self.u8()?, self.u8()?))
+    }
+
+    fn dpus(&mut self) -> Option<Vec<DpuId>> {
+        let length = self.u32()?;
+        (0..length).map(|_| self.dpu()).collect()
+    }
+
+    fn iram_sections(&mut self) -> Option<IramSections> {
+        let nr_of_sections = self.u32()?;
+        (0..nr_of_sections)
+            .map(|_| {
+                let offset = self.u16()?;
+                let length = self.u32()?;
+                let instructions = (0..length).map(|_| self.u64()).collect::<Option<Vec<_>>>()?;
+                Some((offset, instructions))
+            })
+            .collect()
+    }
+
+    fn wram_sections(&mut self) -> Option<WramSections> {
+        let nr_of_sections = self.u32()?;
+        (0..nr_of_sections)
+            .map(|_| {
+                let offset = self.u32()?;
+                let length = self.u32()?;
+                let words = (0..length).map(|_| self.u32()).collect::<Option<Vec<_>>>()?;
+                Some((offset, words))
+            })
+            .collect()
+    }
+
+    fn mram_sections(&mut self) -> Option<MramSections> {
+        let nr_of_sections = self.u32()?;
+        (0..nr_of_sections)
+            .map(|_| Some((self.u32()?, self.bytes()?)))
+            .collect()
+    }
+}
+
+impl Message for Request {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            Request::Hello { version } => {
+                encoder.u8(0);
+                encoder.u16(*version);
+            },
+            Request::Lease(nr_of_dpus) => {
+                encoder.u8(1);
+                encoder.u32(*nr_of_dpus);
+            },
+            Request::Release(dpus) => {
+                encoder.u8(2);
+                encoder.dpus(dpus);
+            },
+            Request::Load(dpus, iram_sections, wram_sections, mram_sections) => {
+                encoder.u8(3);
+                encoder.dpus(dpus);
+                encoder.iram_sections(iram_sections);
+                encoder.wram_sections(wram_sections);
+                encoder.mram_sections(mram_sections);
+            },
+            Request::Boot(dpus) => {
+                encoder.u8(4);
+                encoder.dpus(dpus);
+            },
+            Request::Poll(dpus) => {
+                encoder.u8(5);
+                encoder.dpus(dpus);
+            },
+            Request::CopyTo { dpu, offset, data } => {
+                encoder.u8(6);
+                encoder.dpu(dpu);
+                encoder.u32(*offset);
+                encoder.bytes(data);
+            },
+            Request::CopyFrom { dpu, offset, length } => {
+                encoder.u8(7);
+                encoder.dpu(dpu);
+                encoder.u32(*offset);
+                encoder.u32(*length);
+            },
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        let request = match decoder.u8()? {
+            0 => Request::Hello { version: decoder.u16()? },
+            1 => Request::Lease(decoder.u32()?),
+            2 => Request::Release(decoder.dpus()?),
+            3 => Request::Load(decoder.dpus()?, decoder.iram_sections()?, decoder.wram_sections()?, decoder.mram_sections()?),
+            4 => Request::Boot(decoder.dpus()?),
+            5 => Request::Poll(decoder.dpus()?),
+            6 => Request::CopyTo { dpu: decoder.dpu()?, offset: decoder.u32()?, data: decoder.bytes()? },
+            7 => Request::CopyFrom { dpu: decoder.dpu()?, offset: decoder.u32()?, length: decoder.u32()? },
+            _ => return None,
+        };
+
+        Some(request)
+    }
+}
+
+impl Message for DaemonError {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            DaemonError::UnsupportedVersion(version) => {
+                encoder.u8(0);
+                encoder.u16(*version);
+            },
+            DaemonError::InvalidRequest => encoder.u8(1),
+            DaemonError::NotOwner(dpu) => {
+                encoder.u8(2);
+                encoder.dpu(dpu);
+            },
+            DaemonError::NotEnoughResources { expected, found } => {
+                encoder.u8(3);
+                encoder.u32(*expected);
+                encoder.u32(*found);
+            },
+            DaemonError::DpuIsAlreadyRunning => encoder.u8(4),
+            DaemonError::ClusterFailure(description) => {
+                encoder.u8(5);
+                encoder.string(description);
+            },
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        let error = match decoder.u8()? {
+            0 => DaemonError::UnsupportedVersion(decoder.u16()?),
+            1 => DaemonError::InvalidRequest,
+            2 => DaemonError::NotOwner(decoder.dpu()?),
+            3 => DaemonError::NotEnoughResources { expected: decoder.u32()?, found: decoder.u32()? },
+            4 => DaemonError::DpuIsAlreadyRunning,
+            5 => DaemonError::ClusterFailure(decoder.string()?),
+            _ => return None,
+        };
+
+        Some(error)
+    }
+}
+
+impl Message for Response {
+    fn encode(&self, encoder: &mut Encoder) {
+        match self {
+            Response::Hello(version) => {
+                encoder.u8(0);
+                encoder.u16(*version);
+            },
+            Response::Done => encoder.u8(1),
+            Response::Leased(dpus) => {
+                encoder.u8(2);
+                encoder.dpus(dpus);
+            },
+            Response::Status(RunStatus::Idle) => encoder.u8(3),
+            Response::Status(RunStatus::Running) => encoder.u8(4),
+            Response::Status(RunStatus::Fault(dpus)) => {
+                encoder.u8(5);
+                encoder.dpus(dpus);
+            },
+            Response::Status(RunStatus::TimedOut(dpus)) => {
+                encoder.u8(8);
+                encoder.dpus(dpus);
+            },
+            Response::Data(data) => {
+                encoder.u8(6);
+                encoder.bytes(data);
+            },
+            Response::Error(error) => {
+                encoder.u8(7);
+                error.encode(encoder);
+            },
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'_>) -> Option<Self> {
+        let response = match decoder.u8()? {
+            0 => Response::Hello(decoder.u16()?),
+            1 => Response::Done,
+            2 => Response::Leased(decoder.dpus()?),
+            3 => Response::Status(RunStatus::Idle),
+            4 => Response::Status(RunStatus::Running),
+            5 => Response::Status(RunStatus::Fault(decoder.dpus()?)),
+            6 => Response::Data(decoder.bytes()?),
+            7 => Response::Error(DaemonError::decode(decoder)?),
+            8 => Response::Status(RunStatus::TimedOut(decoder.dpus()?)),
+            _ => return None,
+        };
+
+        Some(response)
+    }
+}
+
+impl From<ClusterError> for DaemonError {
+    fn from(err: ClusterError) -> Self {
+        match err {
+            ClusterError::NotEnoughResources { expected, found } => DaemonError::NotEnoughResources { expected, found },
+            ClusterError::DpuIsAlreadyRunning => DaemonError::DpuIsAlreadyRunning,
+            err => DaemonError::ClusterFailure(format!("{:?}", err)),
+        }
+    }
+}
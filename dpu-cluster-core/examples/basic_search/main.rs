@@ -1,6 +1,7 @@
 use dpu_cluster_core::config::ClusterConfiguration;
 use dpu_cluster_core::cluster::Cluster;
 use dpu_cluster_core::program::Program;
+use dpu_cluster_core::program::ProgramError;
 use dpu_cluster_core::error::ClusterError;
 use dpu_cluster_core::pipeline::PipelineError;
 use std::io;
@@ -27,14 +28,15 @@ const INPUT_BUFFER_ADDRESS: u32 = QUERY_BUFFER_ADDRESS + QUERY_BUFFER_SIZE;
 const OUTPUT_BUFFER_ADDRESS: u32 = INPUT_BUFFER_ADDRESS + INPUT_BUFFER_SIZE;
 
 // The DPU program and the input files can be generated
-const DPU_PROGRAM_IRAM: &'static str = "dpu.iram";
-const DPU_PROGRAM_WRAM: &'static str = "dpu.wram";
+const DPU_PROGRAM: &str = "dpu.elf";
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum AppError {
     DpuInitError(ClusterError),
     DpuError(PipelineError),
     FileManagementError(io::Error),
+    InvalidProgram(ProgramError),
     InvalidQueryId(usize)
 }
 
@@ -82,10 +84,7 @@ impl Iterator for Context {
                     let fragment_id = *next_fragment_id;
                     *next_fragment_id += 1;
 
-                    let transfer = InputMemoryTransfer {
-                        offset: INPUT_BUFFER_ADDRESS,
-                        content: buffer
-                    };
+                    let transfer = InputMemoryTransfer::from_u8_vec(INPUT_BUFFER_ADDRESS, buffer);
 
                     Some((fragment_id, transfer))
                 }
@@ -98,7 +97,7 @@ impl Iterator for Context {
 const DATA_FILE: &str = "data.txt";
 
 fn main() -> Result<(), AppError> {
-    let queries = vec![
+    let queries = [
         Query::new("Artemis"),
         Query::new("turtle"),
         Query::new("Schroedinger")
@@ -156,7 +155,7 @@ fn main() -> Result<(), AppError> {
 }
 
 fn fetch_dpu_program() -> Result<Program, AppError> {
-    Ok(Program::new_raw(std::fs::read(DPU_PROGRAM_IRAM)?, std::fs::read(DPU_PROGRAM_WRAM)?))
+    Ok(Program::from_elf(DPU_PROGRAM)?)
 }
 
 fn map_input_query(input: Input) -> (usize, MemoryTransfers<usize>) {
@@ -164,14 +163,12 @@ fn map_input_query(input: Input) -> (usize, MemoryTransfers<usize>) {
     query_content.resize(QUERY_BUFFER_SIZE as usize, 0);
 
     let transfers = MemoryTransfers {
-        inputs: vec![InputMemoryTransfer {
-            offset: QUERY_BUFFER_ADDRESS,
-            content: query_content
-        }],
+        inputs: vec![InputMemoryTransfer::broadcast(QUERY_BUFFER_ADDRESS, query_content)],
         output: OutputMemoryTransfer {
             offset: OUTPUT_BUFFER_ADDRESS,
             length: OUTPUT_BUFFER_SIZE
         },
+        program: None,
         key: input.id
     };
 
@@ -187,7 +184,7 @@ fn process_outputs(name: &str, output: Vec<u8>) -> Result<(), AppError> {
 
     let output_results = &output.as_slice()[8..(8 + output_bytes as usize)];
 
-    file.write(output_results)?;
+    file.write_all(output_results)?;
 
     Ok(())
 }
@@ -208,4 +205,10 @@ impl From<io::Error> for AppError {
     fn from(err: io::Error) -> Self {
         AppError::FileManagementError(err)
     }
-}
\ No newline at end of file
+}
+
+impl From<ProgramError> for AppError {
+    fn from(err: ProgramError) -> Self {
+        AppError::InvalidProgram(err)
+    }
+}
@@ -4,6 +4,7 @@ use dpu_cluster_core::error::ClusterError;
 use dpu_cluster_core::driver::Driver;
 use dpu_cluster_core::view::View;
 use dpu_cluster_core::program::Program;
+use dpu_cluster_core::program::ProgramError;
 use std::collections::HashMap;
 use std::io;
 use std::fs::File;
@@ -12,7 +13,8 @@ use std::io::Write;
 use std::io::BufReader;
 use std::io::BufRead;
 use dpu_cluster_core::dpu::DpuId;
-use dpu_cluster_core::memory::MemoryTransfer;
+use dpu_cluster_core::memory::FromDpuTransfer;
+use dpu_cluster_core::memory::ToDpuTransfer;
 
 const NB_WORD_MAX: u32 = 7450;
 
@@ -26,16 +28,17 @@ const ADDRESSES_OFFSET: u32 = STATS_OFFSET + STATS_SIZE;
 const STRINGS_OFFSET: u32 = ADDRESSES_OFFSET + ADDRESSES_SIZE;
 
 // The DPU program and the input files can be generated in the main sort_strings repo
-const DPU_PROGRAM_IRAM: &'static str = "dpu.iram";
-const DPU_PROGRAM_WRAM: &'static str = "dpu.wram";
+const DPU_PROGRAM: &str = "dpu.elf";
 
-const INPUT_FILE: &'static str = "input.txt";
-const OUTPUT_FILE: &'static str = "output.txt";
+const INPUT_FILE: &str = "input.txt";
+const OUTPUT_FILE: &str = "output.txt";
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum AppError {
     DpuError(ClusterError),
     FileManagementError(io::Error),
+    InvalidProgram(ProgramError),
     InvalidStringEntry(u32),
     InputFileTooBig(usize)
 }
@@ -53,13 +56,13 @@ fn do_sort(driver: &Driver, input_file: &str, output_file: &str) -> Result<(), A
     let dpu = DpuId::new(0, 0, 0);
     let view = View::one(dpu);
     let program = fetch_dpu_program()?;
-    let (mut strings, mut addresses, string_map) = extract_inputs(input_file, mram_size)?;
-    let mut nb_of_words = vec![addresses.len() as u32, 0];
+    let (strings, mut addresses, string_map) = extract_inputs(input_file, mram_size)?;
+    let nb_of_words = vec![addresses.len() as u32, 0];
 
     driver.load(&view, &program)?;
 
     {
-        let mut input_tranfers = prepare_input_memory_transfers(dpu, &mut strings, &mut addresses, &mut nb_of_words);
+        let mut input_tranfers = prepare_input_memory_transfers(dpu, &strings, &addresses, &nb_of_words);
         for transfer in input_tranfers.iter_mut() {
             driver.copy_to_memory(transfer)?;
         }
@@ -76,9 +79,10 @@ fn do_sort(driver: &Driver, input_file: &str, output_file: &str) -> Result<(), A
 }
 
 fn fetch_dpu_program() -> Result<Program, AppError> {
-    Ok(Program::new_raw(std::fs::read(DPU_PROGRAM_IRAM)?, std::fs::read(DPU_PROGRAM_WRAM)?))
+    Ok(Program::from_elf(DPU_PROGRAM)?)
 }
 
+#[allow(clippy::type_complexity)]
 fn extract_inputs(filename: &str, mram_size: u32) -> Result<(Vec<u8>, Vec<u32>, HashMap<u32, String>), AppError> {
     let file = File::open(filename)?;
     let file = BufReader::new(file);
@@ -108,25 +112,25 @@ fn extract_inputs(filename: &str, mram_size: u32) -> Result<(Vec<u8>, Vec<u32>,
     Ok((strings, string_addresses, string_map))
 }
 
-fn prepare_input_memory_transfers<'a>(dpu: DpuId, strings: &'a mut [u8], addresses: &'a mut [u32], nb_of_words: &'a mut [u32]) -> Vec<MemoryTransfer<'a>> {
-    let strings_tranfer = MemoryTransfer::default().add(dpu, STRINGS_OFFSET, strings);
-    let addresses_tranfer = MemoryTransfer::default().add(dpu, ADDRESSES_OFFSET, addresses);
-    let nb_of_words_tranfer = MemoryTransfer::default().add(dpu, NB_OF_WORDS_OFFSET, nb_of_words);
+fn prepare_input_memory_transfers<'a>(dpu: DpuId, strings: &'a [u8], addresses: &'a [u32], nb_of_words: &'a [u32]) -> Vec<ToDpuTransfer<'a>> {
+    let strings_tranfer = ToDpuTransfer::default().bouncing_unaligned_regions().add(dpu, STRINGS_OFFSET, strings);
+    let addresses_tranfer = ToDpuTransfer::default().bouncing_unaligned_regions().add(dpu, ADDRESSES_OFFSET, addresses);
+    let nb_of_words_tranfer = ToDpuTransfer::default().bouncing_unaligned_regions().add(dpu, NB_OF_WORDS_OFFSET, nb_of_words);
 
     vec![strings_tranfer, addresses_tranfer, nb_of_words_tranfer]
 }
 
-fn prepare_output_memory_transfer(dpu: DpuId, output: &mut [u32]) -> MemoryTransfer<'_> {
-    MemoryTransfer::default().add(dpu, ADDRESSES_OFFSET, output)
+fn prepare_output_memory_transfer(dpu: DpuId, output: &mut [u32]) -> FromDpuTransfer<'_> {
+    FromDpuTransfer::default().bouncing_unaligned_regions().add(dpu, ADDRESSES_OFFSET, output)
 }
 
 fn process_outputs(output: Vec<u32>, filename: &str, string_map: HashMap<u32, String>) -> Result<(), AppError> {
     let mut file = OpenOptions::new().write(true).create(true).truncate(true).open(filename)?;
 
     for entry in output {
-        let string = string_map.get(&entry).ok_or_else(|| AppError::InvalidStringEntry(entry))?;
-        file.write(string.as_bytes())?;
-        file.write(b"\n")?;
+        let string = string_map.get(&entry).ok_or(AppError::InvalidStringEntry(entry))?;
+        file.write_all(string.as_bytes())?;
+        file.write_all(b"\n")?;
     }
 
     Ok(())
@@ -142,4 +146,10 @@ impl From<io::Error> for AppError {
     fn from(err: io::Error) -> Self {
         AppError::FileManagementError(err)
     }
-}
\ No newline at end of file
+}
+
+impl From<ProgramError> for AppError {
+    fn from(err: ProgramError) -> Self {
+        AppError::InvalidProgram(err)
+    }
+}
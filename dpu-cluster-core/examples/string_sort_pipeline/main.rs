@@ -1,6 +1,7 @@
 use dpu_cluster_core::config::ClusterConfiguration;
 use dpu_cluster_core::cluster::Cluster;
 use dpu_cluster_core::program::Program;
+use dpu_cluster_core::program::ProgramError;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
@@ -26,18 +27,19 @@ const ADDRESSES_OFFSET: u32 = STATS_OFFSET + STATS_SIZE;
 const STRINGS_OFFSET: u32 = ADDRESSES_OFFSET + ADDRESSES_SIZE;
 
 // The DPU program and the input files can be generated in the main sort_strings repo
-const DPU_PROGRAM_IRAM: &'static str = "dpu.iram";
-const DPU_PROGRAM_WRAM: &'static str = "dpu.wram";
+const DPU_PROGRAM: &str = "dpu.elf";
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum AppError {
     DpuInitError(ClusterError),
     DpuError(PipelineError),
     FileManagementError(io::Error),
+    InvalidProgram(ProgramError),
     InputFileTooBig(usize)
 }
 
-const INPUTS: &'static [&'static str] = &["input.txt"; 16];
+const INPUTS: &[&str] = &["input.txt"; 16];
 
 fn main() -> Result<(), AppError> {
     let config = ClusterConfiguration::for_functional_simulator(1);
@@ -66,7 +68,7 @@ fn main() -> Result<(), AppError> {
 }
 
 fn fetch_dpu_program() -> Result<Program, AppError> {
-    Ok(Program::new_raw(std::fs::read(DPU_PROGRAM_IRAM)?, std::fs::read(DPU_PROGRAM_WRAM)?))
+    Ok(Program::from_elf(DPU_PROGRAM)?)
 }
 
 fn map_transfers(input: (usize, Vec<u8>, Vec<u32>)) -> MemoryTransfers<usize> {
@@ -83,6 +85,7 @@ fn map_transfers(input: (usize, Vec<u8>, Vec<u32>)) -> MemoryTransfers<usize> {
             offset: ADDRESSES_OFFSET,
             length: nr_of_words * 4
         },
+        program: None,
         key: idx
     }
 }
@@ -121,14 +124,14 @@ fn process_outputs(output: Vec<u8>, filename: &str) -> Result<(), AppError> {
         let index = {
             let mut val = 0u32;
             for (idx, byte) in entry.iter().enumerate() {
-                val = val | (((*byte as u32) & 0xff) << (idx * 8));
+                val |= ((*byte as u32) & 0xff) << (idx * 8);
             }
             val
         };
 
         let index_as_string = format!("{}", index);
-        file.write(index_as_string.as_bytes())?;
-        file.write(b"\n")?;
+        file.write_all(index_as_string.as_bytes())?;
+        file.write_all(b"\n")?;
     }
 
     Ok(())
@@ -150,4 +153,10 @@ impl From<io::Error> for AppError {
     fn from(err: io::Error) -> Self {
         AppError::FileManagementError(err)
     }
-}
\ No newline at end of file
+}
+
+impl From<ProgramError> for AppError {
+    fn from(err: ProgramError) -> Self {
+        AppError::InvalidProgram(err)
+    }
+}
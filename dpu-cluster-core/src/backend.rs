@@ -0,0 +1,189 @@
+use std::fmt::Debug;
+use dpu_sys::DpuRank;
+use dpu_sys::DpuError;
+use dpu_sys::DpuTarget;
+use dpu_sys::DpuRankDescription;
+use dpu_sys::DpuDebugContext;
+use dpu_sys::DpuRankTransferMatrix;
+use dpu_sys::RankBitmap;
+use crate::memory::MemoryTransferRankEntry;
+use crate::memory::TransferBuffer;
+
+const PRIMARY_MRAM: u32 = 0;
+
+pub trait RankBackend: Debug + Send + Sync {
+    fn reset_all(&self) -> Result<(), DpuError>;
+    fn reset_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError>;
+
+    fn launch_thread_on_all(&self, thread: u8, should_resume: bool) -> Result<RankBitmap, DpuError>;
+    fn launch_thread_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, should_resume: bool) -> Result<bool, DpuError>;
+    fn poll_all(&self) -> Result<(RankBitmap, RankBitmap), DpuError>;
+    fn poll_dpu(&self, slice_id: u8, member_id: u8) -> Result<(bool, bool), DpuError>;
+    fn fault_all(&self) -> Result<(), DpuError>;
+    fn fault_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError>;
+
+    fn copy_to_irams(&self, instructions: &[u64], offset: u16) -> Result<(), DpuError>;
+    fn copy_to_iram(&self, slice_id: u8, member_id: u8, instructions: &[u64], offset: u16) -> Result<(), DpuError>;
+    fn copy_from_iram(&self, slice_id: u8, member_id: u8, instructions: &mut [u64], offset: u16) -> Result<(), DpuError>;
+    fn copy_to_wrams(&self, data: &[u32], offset: u32) -> Result<(), DpuError>;
+    fn copy_to_wram(&self, slice_id: u8, member_id: u8, data: &[u32], offset: u32) -> Result<(), DpuError>;
+    fn copy_from_wram(&self, slice_id: u8, member_id: u8, data: &mut [u32], offset: u32) -> Result<(), DpuError>;
+    fn copy_to_mram(&self, slice_id: u8, member_id: u8, data: &[u8], offset: u32) -> Result<(), DpuError>;
+    fn copy_from_mram(&self, slice_id: u8, member_id: u8, data: &mut [u8], offset: u32) -> Result<(), DpuError>;
+    fn copy_to_mrams(&self, transfers: &mut MemoryTransferRankEntry<&[u8]>) -> Result<(), DpuError>;
+    fn copy_from_mrams(&self, transfers: &mut MemoryTransferRankEntry<&mut [u8]>) -> Result<(), DpuError>;
+
+    fn extract_context_from_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError>;
+    fn initialize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError>;
+    fn execute_thread_step_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, context: &mut DpuDebugContext) -> Result<(), DpuError>;
+    fn finalize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError>;
+}
+
+pub trait RankAllocator {
+    // finds at most the given number of ranks, when it is known
+    fn find_available_ranks_for(&self, target: &DpuTarget, nr_of_ranks_needed: Option<usize>) -> Result<Vec<DpuRankDescription>, DpuError>;
+    fn get_description_for(&self, target: &DpuTarget) -> Result<DpuRankDescription, DpuError>;
+    fn nr_of_ranks_for(&self, target: &DpuTarget) -> usize;
+    fn allocate_for(&self, target: &DpuTarget) -> Result<Box<dyn RankBackend>, DpuError>;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CniAllocator;
+
+impl RankAllocator for CniAllocator {
+    fn find_available_ranks_for(&self, target: &DpuTarget, nr_of_ranks_needed: Option<usize>) -> Result<Vec<DpuRankDescription>, DpuError> {
+        let (dpu_type, ref profile) = target.to_cni_args();
+        DpuRank::find_available_ranks_for(dpu_type, profile, nr_of_ranks_needed)
+    }
+
+    fn get_description_for(&self, target: &DpuTarget) -> Result<DpuRankDescription, DpuError> {
+        let (dpu_type, ref profile) = target.to_cni_args();
+        DpuRank::get_description_for(dpu_type, profile)
+    }
+
+    fn nr_of_ranks_for(&self, target: &DpuTarget) -> usize {
+        DpuRank::nr_of_ranks_for(target.dpu_type)
+    }
+
+    fn allocate_for(&self, target: &DpuTarget) -> Result<Box<dyn RankBackend>, DpuError> {
+        let (dpu_type, ref profile) = target.to_cni_args();
+        let rank = DpuRank::allocate_for(dpu_type, profile)?;
+
+        Ok(Box::new(rank))
+    }
+}
+
+impl RankBackend for DpuRank {
+    fn reset_all(&self) -> Result<(), DpuError> {
+        DpuRank::reset_all(self)
+    }
+
+    fn reset_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError> {
+        DpuRank::reset_dpu(self, slice_id, member_id)
+    }
+
+    fn launch_thread_on_all(&self, thread: u8, should_resume: bool) -> Result<RankBitmap, DpuError> {
+        DpuRank::launch_thread_on_all(self, thread, should_resume)
+    }
+
+    fn launch_thread_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, should_resume: bool) -> Result<bool, DpuError> {
+        DpuRank::launch_thread_on_dpu(self, slice_id, member_id, thread, should_resume)
+    }
+
+    fn poll_all(&self) -> Result<(RankBitmap, RankBitmap), DpuError> {
+        DpuRank::poll_all(self)
+    }
+
+    fn poll_dpu(&self, slice_id: u8, member_id: u8) -> Result<(bool, bool), DpuError> {
+        DpuRank::poll_dpu(self, slice_id, member_id)
+    }
+
+    fn fault_all(&self) -> Result<(), DpuError> {
+        DpuRank::fault_all(self)
+    }
+
+    fn fault_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError> {
+        DpuRank::fault_dpu(self, slice_id, member_id)
+    }
+
+    fn copy_to_irams(&self, instructions: &[u64], offset: u16) -> Result<(), DpuError> {
+        DpuRank::copy_to_irams(self, instructions.as_ptr(), instructions.len() as u16, offset)
+    }
+
+    fn copy_to_iram(&self, slice_id: u8, member_id: u8, instructions: &[u64], offset: u16) -> Result<(), DpuError> {
+        DpuRank::copy_to_iram(self, slice_id, member_id, instructions.as_ptr(), instructions.len() as u16, offset)
+    }
+
+    fn copy_from_iram(&self, slice_id: u8, member_id: u8, instructions: &mut [u64], offset: u16) -> Result<(), DpuError> {
+        DpuRank::copy_from_iram(self, slice_id, member_id, instructions.as_mut_ptr(), instructions.len() as u16, offset)
+    }
+
+    fn copy_to_wrams(&self, data: &[u32], offset: u32) -> Result<(), DpuError> {
+        DpuRank::copy_to_wrams(self, data.as_ptr(), data.len() as u32, offset)
+    }
+
+    fn copy_to_wram(&self, slice_id: u8, member_id: u8, data: &[u32], offset: u32) -> Result<(), DpuError> {
+        DpuRank::copy_to_wram(self, slice_id, member_id, data.as_ptr(), data.len() as u32, offset)
+    }
+
+    fn copy_from_wram(&self, slice_id: u8, member_id: u8, data: &mut [u32], offset: u32) -> Result<(), DpuError> {
+        DpuRank::copy_from_wram(self, slice_id, member_id, data.as_mut_ptr(), data.len() as u32, offset)
+    }
+
+    fn copy_to_mram(&self, slice_id: u8, member_id: u8, data: &[u8], offset: u32) -> Result<(), DpuError> {
+        DpuRank::copy_to_mram(self, slice_id, member_id, data.as_ptr(), data.len() as u32, offset, PRIMARY_MRAM)
+    }
+
+    fn copy_from_mram(&self, slice_id: u8, member_id: u8, data: &mut [u8], offset: u32) -> Result<(), DpuError> {
+        DpuRank::copy_from_mram(self, slice_id, member_id, data.as_mut_ptr(), data.len() as u32, offset, PRIMARY_MRAM)
+    }
+
+    fn copy_to_mrams(&self, transfers: &mut MemoryTransferRankEntry<&[u8]>) -> Result<(), DpuError> {
+        for region in 0..transfers.nr_of_regions() {
+            let matrix = create_transfer_matrix_for(self, transfers, region)?;
+            DpuRank::copy_to_mrams(self, &matrix)?;
+        }
+
+        Ok(())
+    }
+
+    fn copy_from_mrams(&self, transfers: &mut MemoryTransferRankEntry<&mut [u8]>) -> Result<(), DpuError> {
+        for region in 0..transfers.nr_of_regions() {
+            let matrix = create_transfer_matrix_for(self, transfers, region)?;
+            DpuRank::copy_from_mrams(self, &matrix)?;
+        }
+
+        Ok(())
+    }
+
+    fn extract_context_from_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
+        DpuRank::extract_context_from_dpu(self, slice_id, member_id, context)
+    }
+
+    fn initialize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
+        DpuRank::initialize_fault_process_for_dpu(self, slice_id, member_id, context)
+    }
+
+    fn execute_thread_step_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
+        DpuRank::execute_thread_step_on_dpu(self, slice_id, member_id, thread, context)
+    }
+
+    fn finalize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
+        DpuRank::finalize_fault_process_for_dpu(self, slice_id, member_id, context)
+    }
+}
+
+// A matrix holds at most one region per DPU: the n-th matrix holds the n-th region of every DPU.
+fn create_transfer_matrix_for<'a, B: TransferBuffer>(rank: &'a DpuRank, data: &mut MemoryTransferRankEntry<B>, region: usize) -> Result<DpuRankTransferMatrix<'a>, DpuError> {
+    let matrix = DpuRankTransferMatrix::allocate_for(rank)?;
+
+    for (dpu, image) in data.0.iter_mut().filter_map(|(dpu, images)| images.get_mut(region).map(|image| (dpu, image))) {
+        let (_, slice, member) = dpu.members();
+        let offset = image.offset;
+        let length = image.reference.as_ref().len() as u32;
+
+        matrix.add_dpu(slice, member, image.reference.as_transfer_ptr(), length, offset, PRIMARY_MRAM);
+    }
+
+    Ok(matrix)
+}
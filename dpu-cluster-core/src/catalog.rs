@@ -0,0 +1,181 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use crate::dpu::DpuId;
+use crate::error::ClusterError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentLocation {
+    pub dpu: DpuId,
+    pub offset: u32,
+    pub length: u32,
+    // increases with every fragment recorded in the catalog
+    pub version: u64
+}
+
+impl FragmentLocation {
+    fn overlaps(&self, dpu: &DpuId, offset: u32, length: u32) -> bool {
+        self.dpu == *dpu &&
+            (self.offset as u64) < (offset as u64) + (length as u64) &&
+            (offset as u64) < (self.offset as u64) + (self.length as u64)
+    }
+}
+
+// The fragment ids of a dataset are typed by its user: the catalog only needs to see their locations.
+trait Fragments: Send {
+    fn retain(&mut self, keep: &dyn Fn(&FragmentLocation) -> bool);
+    fn any(&self, matches: &dyn Fn(&FragmentLocation) -> bool) -> bool;
+    fn is_empty(&self) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl <D: Eq + Hash + Send + 'static> Fragments for HashMap<D, FragmentLocation> {
+    fn retain(&mut self, keep: &dyn Fn(&FragmentLocation) -> bool) {
+        HashMap::retain(self, |_, location| keep(location))
+    }
+
+    fn any(&self, matches: &dyn Fn(&FragmentLocation) -> bool) -> bool {
+        self.values().any(matches)
+    }
+
+    fn is_empty(&self) -> bool {
+        HashMap::is_empty(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Tracks which fragments of which datasets live where in the MRAMs of a cluster. Recording a fragment
+// forgets the ones it overwrites, whatever their dataset.
+#[derive(Default)]
+pub struct DataCatalog {
+    datasets: Mutex<HashMap<String, Box<dyn Fragments>>>,
+    last_version: AtomicU64,
+    nr_of_anonymous_datasets: AtomicU64
+}
+
+impl fmt::Debug for DataCatalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataCatalog")
+            .field("datasets", &self.datasets())
+            .finish()
+    }
+}
+
+impl DataCatalog {
+    pub fn record<D>(&self, dataset: &str, fragment: D, dpu: DpuId, offset: u32, length: u32) -> Result<FragmentLocation, ClusterError>
+        where D: Eq + Hash + Send + 'static
+    {
+        // unwrap: the lock is never held across a panicking operation
+        let mut datasets = self.datasets.lock().unwrap();
+
+        if datasets.get(dataset).is_some_and(|fragments| fragments.as_any().downcast_ref::<HashMap<D, FragmentLocation>>().is_none()) {
+            return Err(ClusterError::DatasetTypeMismatch(dataset.to_string()));
+        }
+
+        forget_overlapping_in(&mut datasets, &dpu, offset, length);
+
+        let version = self.last_version.fetch_add(1, Ordering::SeqCst) + 1;
+        let location = FragmentLocation { dpu, offset, length, version };
+
+        datasets.entry(dataset.to_string())
+            .or_insert_with(|| Box::new(HashMap::<D, FragmentLocation>::default()))
+            .as_any_mut()
+            .downcast_mut::<HashMap<D, FragmentLocation>>()
+            // unwrap: the type of the dataset has been checked above
+            .unwrap()
+            .insert(fragment, location);
+
+        Ok(location)
+    }
+
+    pub fn locate<D>(&self, dataset: &str, fragment: &D) -> Option<FragmentLocation>
+        where D: Eq + Hash + Send + 'static
+    {
+        self.with_fragments(dataset, |fragments: &HashMap<D, FragmentLocation>| fragments.get(fragment).copied())
+            .flatten()
+    }
+
+    pub fn fragments_on<D>(&self, dataset: &str, dpu: &DpuId) -> Vec<(D, FragmentLocation)>
+        where D: Eq + Hash + Clone + Send + 'static
+    {
+        self.with_fragments(dataset, |fragments: &HashMap<D, FragmentLocation>| {
+            fragments.iter()
+                .filter(|(_, location)| location.dpu == *dpu)
+                .map(|(fragment, location)| (fragment.clone(), *location))
+                .collect()
+        }).unwrap_or_default()
+    }
+
+    pub fn holds_fragments_on(&self, dataset: &str, dpu: &DpuId) -> bool {
+        let datasets = self.datasets.lock().unwrap();
+
+        datasets.get(dataset).is_some_and(|fragments| fragments.any(&|location| location.dpu == *dpu))
+    }
+
+    pub fn remove<D>(&self, dataset: &str, fragment: &D) -> Option<FragmentLocation>
+        where D: Eq + Hash + Send + 'static
+    {
+        let mut datasets = self.datasets.lock().unwrap();
+
+        datasets.get_mut(dataset)
+            .and_then(|fragments| fragments.as_any_mut().downcast_mut::<HashMap<D, FragmentLocation>>())
+            .and_then(|fragments| fragments.remove(fragment))
+    }
+
+    pub fn remove_dataset(&self, dataset: &str) {
+        self.datasets.lock().unwrap().remove(dataset);
+    }
+
+    pub(crate) fn forget_overlapping(&self, dpu: &DpuId, offset: u32, length: u32) {
+        forget_overlapping_in(&mut self.datasets.lock().unwrap(), dpu, offset, length);
+    }
+
+    // to be called when the programs running on these DPUs overwrite their MRAM
+    pub fn invalidate(&self, dpus: &[DpuId]) {
+        let mut datasets = self.datasets.lock().unwrap();
+
+        for fragments in datasets.values_mut() {
+            fragments.retain(&|location| !dpus.contains(&location.dpu));
+        }
+        datasets.retain(|_, fragments| !fragments.is_empty());
+    }
+
+    // for the datasets which do not outlive the plan that loaded them
+    pub(crate) fn anonymous_dataset(&self) -> String {
+        format!("#{}", self.nr_of_anonymous_datasets.fetch_add(1, Ordering::SeqCst))
+    }
+
+    pub fn datasets(&self) -> Vec<String> {
+        self.datasets.lock().unwrap().keys().cloned().collect()
+    }
+
+    fn with_fragments<D, R, F>(&self, dataset: &str, f: F) -> Option<R>
+        where D: Eq + Hash + Send + 'static,
+              F: FnOnce(&HashMap<D, FragmentLocation>) -> R
+    {
+        let datasets = self.datasets.lock().unwrap();
+
+        datasets.get(dataset)
+            .and_then(|fragments| fragments.as_any().downcast_ref::<HashMap<D, FragmentLocation>>())
+            .map(f)
+    }
+}
+
+fn forget_overlapping_in(datasets: &mut HashMap<String, Box<dyn Fragments>>, dpu: &DpuId, offset: u32, length: u32) {
+    for fragments in datasets.values_mut() {
+        fragments.retain(&|location| !location.overlaps(dpu, offset, length));
+    }
+    datasets.retain(|_, fragments| !fragments.is_empty());
+}
@@ -1,31 +1,79 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use crate::catalog::DataCatalog;
 use crate::config::ClusterConfiguration;
 use crate::driver::Driver;
-use dpu_sys::DpuRank;
 use dpu_sys::DpuRankDescription;
 use dpu_sys::DpuTarget;
 use crate::error::ClusterError;
 use crate::dpu::Mapping;
 use crate::dpu::DpuId;
+use crate::dpu::ProcessId;
+use crate::backend::RankAllocator;
+use crate::backend::RankBackend;
+use crate::backend::CniAllocator;
+use crate::debugger::Debugger;
+use crate::lock;
+use crate::lock::FileLock;
+use crate::lease::DpuLease;
+use crate::lease::LeasePolicy;
+use crate::memory::MramAllocator;
+
+type Ranks = Vec<Box<dyn RankBackend>>;
 
 #[derive(Debug)]
 pub struct Cluster {
     driver: Driver,
-    workers: Mapping
+    workers: Mutex<Mapping>,
+    next_owner: AtomicU64,
+    mram_allocator: Mutex<MramAllocator>,
+    // released after the ranks, which are freed with the driver
+    _locks: Vec<FileLock>
 }
 
 impl Cluster {
     pub fn create(config: ClusterConfiguration) -> Result<Self, ClusterError> {
-        // todo: take system-wide lock for specific dpu target
-        let max_nr_dpus = find_nr_of_available_dpus_for(&config.target)?;
-
-        let nr_of_dpus_expected = config.nr_of_dpus_expected.unwrap_or(max_nr_dpus);
+        Cluster::create_with(config, &CniAllocator)
+    }
 
-        if nr_of_dpus_expected > max_nr_dpus {
-            return Err(ClusterError::NotEnoughResources { expected: nr_of_dpus_expected, found: max_nr_dpus});
-        }
+    pub fn create_with(config: ClusterConfiguration, allocator: &dyn RankAllocator) -> Result<Self, ClusterError> {
+        let dpu_type = config.target.dpu_type;
+        let lock_directory = config.lock_directory.as_deref().unwrap_or_else(|| Path::new(lock::DEFAULT_LOCK_DIRECTORY));
+        let deadline = config.lock_timeout.map(|timeout| Instant::now() + timeout);
+        let rank_description = allocator.get_description_for(&config.target)?;
+        let nr_of_dpus_per_rank = rank_description.topology.nr_of_dpus();
+        let nr_of_ranks_needed = config.nr_of_dpus_expected.map(|nr_of_dpus| nr_of_dpus.div_ceil(nr_of_dpus_per_rank) as usize);
+
+        // the ranks are probed and allocated under the type lock, released while waiting for busy ranks
+        let (ranks, locks) = loop {
+            let type_lock = if lock::needs_lock(dpu_type) {
+                Some(lock::lock_type(lock_directory, dpu_type, deadline)?)
+            } else {
+                None
+            };
+
+            let available_ranks = allocator.find_available_ranks_for(&config.target, nr_of_ranks_needed)?;
+            let max_nr_dpus = available_ranks.iter()
+                .map(|description| description.topology.nr_of_dpus())
+                .sum();
+
+            let nr_of_dpus_expected = config.nr_of_dpus_expected.unwrap_or(max_nr_dpus);
+
+            if nr_of_dpus_expected <= max_nr_dpus {
+                break allocate_at_least(nr_of_dpus_expected, &rank_description, &config, lock_directory, allocator)?;
+            }
 
-        let rank_description = find_description_for(&config.target)?;
-        let ranks = allocate_at_least(nr_of_dpus_expected, &rank_description, &config.target)?;
+            drop(type_lock);
+            // simulated ranks are never released by other processes
+            if !lock::needs_lock(dpu_type) || lock::wait_until(deadline).is_err() {
+                return Err(ClusterError::NotEnoughResources { expected: nr_of_dpus_expected, found: max_nr_dpus});
+            }
+        };
 
         let mut dpu_ids= Vec::default();
         for rank_id in 0..ranks.len() as u8 {
@@ -37,17 +85,72 @@ impl Cluster {
             }
         }
 
-        let driver = Driver::new(ranks, rank_description, config.target);
+        let mut driver = Driver::new(ranks, rank_description, config.target);
+        if let Some(nr_of_rank_workers) = config.nr_of_rank_workers {
+            driver = driver.with_rank_workers(nr_of_rank_workers);
+        }
+        driver.set_verification(config.verification);
 
-        let workers = Mapping::new(dpu_ids);
+        let workers = Mutex::new(Mapping::new(dpu_ids));
+        let mram_allocator = Mutex::new(MramAllocator::new(driver.rank_description.memories.mram_size));
 
-        Ok(Cluster { driver, workers })
+        Ok(Cluster { driver, workers, next_owner: AtomicU64::new(0), mram_allocator, _locks: locks })
     }
 
     pub fn driver(&self) -> &Driver {
         &self.driver
     }
 
+    pub fn catalog(&self) -> &DataCatalog {
+        self.driver.catalog()
+    }
+
+    pub fn new_owner(&self) -> ProcessId {
+        ProcessId(self.next_owner.fetch_add(1, Ordering::SeqCst))
+    }
+
+    pub fn lease(self: &Arc<Self>, nr_of_dpus: u32, policy: LeasePolicy) -> Result<DpuLease, ClusterError> {
+        let owner = self.new_owner();
+        let dpus = self.reserve(owner, nr_of_dpus, policy)?;
+
+        Ok(DpuLease::new(self.clone(), owner, dpus))
+    }
+
+    pub fn reserve(&self, owner: ProcessId, nr_of_dpus: u32, policy: LeasePolicy) -> Result<Vec<DpuId>, ClusterError> {
+        // unwrap: the lock is never held across a panicking operation
+        let mut workers = self.workers.lock().unwrap();
+        let nr_of_available_dpus = workers.nr_of_available_dpus() as u32;
+
+        workers.reserve_many(owner, nr_of_dpus as usize, policy)
+            .ok_or(ClusterError::NotEnoughResources { expected: nr_of_dpus, found: nr_of_available_dpus })
+    }
+
+    pub fn release(&self, owner: &ProcessId, dpus: &[DpuId]) {
+        let mut workers = self.workers.lock().unwrap();
+
+        for dpu in dpus {
+            if workers.owner_of(dpu) == Some(owner) {
+                workers.release(dpu);
+            }
+        }
+    }
+
+    pub fn release_all(&self, owner: &ProcessId) {
+        let mut workers = self.workers.lock().unwrap();
+
+        for dpu in workers.reserved_by(owner) {
+            workers.release(&dpu);
+        }
+    }
+
+    pub fn owner_of(&self, dpu: &DpuId) -> Option<ProcessId> {
+        self.workers.lock().unwrap().owner_of(dpu).copied()
+    }
+
+    pub fn debug(&self, dpu: DpuId) -> Result<Debugger<'_>, ClusterError> {
+        Debugger::attach(&self.driver, dpu)
+    }
+
     pub fn topology(&self) -> (u8, u8, u8) {
         (
             self.driver.nr_of_ranks,
@@ -56,37 +159,39 @@ impl Cluster {
         )
     }
 
+    // the regions are shared by all the users of the cluster
+    pub fn mram_allocator(&self) -> MutexGuard<'_, MramAllocator> {
+        self.mram_allocator.lock().unwrap()
+    }
+
     pub fn target(&self) -> DpuTarget {
         self.driver.target.clone()
     }
 }
 
-fn find_nr_of_available_dpus_for(target: &DpuTarget) -> Result<u32, ClusterError> {
-    let (dpu_type, ref profile) = target.to_cni_args();
-    let nr_of_dpus = DpuRank::find_nr_of_available_dpus_for(dpu_type, profile)?;
-
-    Ok(nr_of_dpus)
-}
-
-fn find_description_for(target: &DpuTarget) -> Result<DpuRankDescription, ClusterError> {
-    let (dpu_type, ref profile) = target.to_cni_args();
-    let description = DpuRank::get_description_for(dpu_type, profile)?;
-
-    Ok(description)
-}
-
-fn allocate_at_least(nr_of_dpus: u32, description: &DpuRankDescription, target: &DpuTarget) -> Result<Vec<DpuRank>, ClusterError> {
-    let nr_of_dpus_per_rank = (description.topology.nr_of_control_interfaces as u32) * (description.topology.nr_of_dpus_per_control_interface as u32);
-    let nr_of_ranks = (nr_of_dpus / nr_of_dpus_per_rank) + if (nr_of_dpus % nr_of_dpus_per_rank) == 0 { 0 } else { 1 };
+fn allocate_at_least(nr_of_dpus: u32,
+                     description: &DpuRankDescription,
+                     config: &ClusterConfiguration,
+                     lock_directory: &Path,
+                     allocator: &dyn RankAllocator) -> Result<(Ranks, Vec<FileLock>), ClusterError> {
+    let nr_of_dpus_per_rank = description.topology.nr_of_dpus();
+    let nr_of_ranks = (nr_of_dpus / nr_of_dpus_per_rank) + if nr_of_dpus.is_multiple_of(nr_of_dpus_per_rank) { 0 } else { 1 };
     let mut ranks = Vec::with_capacity(nr_of_ranks as usize);
+    let mut locks = Vec::default();
+
+    let dpu_type = config.target.dpu_type;
 
-    let (dpu_type, ref profile) = target.to_cni_args();
+    let nr_of_slots = allocator.nr_of_ranks_for(&config.target);
 
     for _ in 0..nr_of_ranks {
-        let rank = DpuRank::allocate_for(dpu_type, profile)?;
+        if lock::needs_lock(dpu_type) {
+            locks.push(lock::lock_rank_slot(lock_directory, dpu_type, nr_of_slots)?);
+        }
+        let rank = allocator.allocate_for(&config.target)?;
+
         rank.reset_all()?;
         ranks.push(rank);
     }
 
-    Ok(ranks)
+    Ok((ranks, locks))
 }
\ No newline at end of file
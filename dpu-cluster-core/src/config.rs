@@ -1,23 +1,54 @@
+use std::path::PathBuf;
+use std::time::Duration;
 use dpu_sys::DpuTarget;
+use crate::driver::Verification;
 
+// Without lock timeout, the cluster creation fails as soon as too many ranks are busy.
 #[derive(Default)]
 pub struct ClusterConfiguration {
     pub target: DpuTarget,
-    pub nr_of_dpus_expected: Option<u32>
+    pub nr_of_dpus_expected: Option<u32>,
+    pub lock_directory: Option<PathBuf>,
+    pub lock_timeout: Option<Duration>,
+    pub verification: Verification,
+    // defaults to 1: the ranks are driven by the calling thread
+    pub nr_of_rank_workers: Option<usize>
 }
 
 impl ClusterConfiguration {
     pub fn for_functional_simulator(nr_of_dpus: u32) -> ClusterConfiguration {
         ClusterConfiguration {
             target: DpuTarget::for_functional_simulator(),
-            nr_of_dpus_expected: Some(nr_of_dpus)
+            nr_of_dpus_expected: Some(nr_of_dpus),
+            ..Default::default()
         }
     }
 
     pub fn for_hardware(nr_of_dpus: u32) -> ClusterConfiguration {
         ClusterConfiguration {
             target: DpuTarget::for_hardware_implementation(),
-            nr_of_dpus_expected: Some(nr_of_dpus)
+            nr_of_dpus_expected: Some(nr_of_dpus),
+            ..Default::default()
         }
     }
-}
\ No newline at end of file
+
+    pub fn lock_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.lock_directory = Some(directory.into());
+        self
+    }
+
+    pub fn wait_for_ranks(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_rank_workers(mut self, nr_of_rank_workers: usize) -> Self {
+        self.nr_of_rank_workers = Some(nr_of_rank_workers);
+        self
+    }
+
+    pub fn verifying_transfers(mut self, verification: Verification) -> Self {
+        self.verification = verification;
+        self
+    }
+}
@@ -0,0 +1,142 @@
+use dpu_sys::DpuDebugContext;
+use crate::dpu::DpuId;
+use crate::driver::Driver;
+use crate::error::ClusterError;
+use crate::fault::FaultReport;
+use crate::memory::FromDpuTransfer;
+use crate::memory::ToDpuTransfer;
+
+const NOT_SCHEDULED: u8 = 0xFF;
+
+// The DPU is kept in its fault process while the session lives: register and pc changes are
+// applied when stepping or resuming, memories are accessed directly. A session dropped without
+// being resumed leaves the fault process, but its threads stay stopped.
+pub struct Debugger<'a> {
+    driver: &'a Driver,
+    dpu: DpuId,
+    context: DpuDebugContext,
+    nr_of_registers: usize,
+    is_finalized: bool
+}
+
+impl <'a> Debugger<'a> {
+    pub fn attach(driver: &'a Driver, dpu: DpuId) -> Result<Self, ClusterError> {
+        let (rank, slice, member) = driver.destructure(&dpu);
+        let mut context = driver.new_debug_context();
+        let nr_of_registers = driver.rank_description.info.nr_of_work_registers_per_thread as usize;
+
+        let (is_running, is_in_fault) = rank.poll_dpu(slice, member)?;
+
+        if is_running && !is_in_fault {
+            rank.fault_dpu(slice, member)?;
+        }
+
+        rank.initialize_fault_process_for_dpu(slice, member, &mut context)?;
+
+        Ok(Debugger { driver, dpu, context, nr_of_registers, is_finalized: false })
+    }
+
+    pub fn dpu(&self) -> DpuId {
+        self.dpu
+    }
+
+    pub fn context(&self) -> &DpuDebugContext {
+        &self.context
+    }
+
+    pub fn report(&self) -> FaultReport {
+        FaultReport::new(self.dpu, &self.context)
+    }
+
+    pub fn running_threads(&self) -> Vec<u8> {
+        self.context.scheduling.iter()
+            .enumerate()
+            .filter(|(_, order)| **order != NOT_SCHEDULED)
+            .map(|(thread, _)| thread as u8)
+            .collect()
+    }
+
+    pub fn pc(&self, thread: u8) -> Option<u16> {
+        self.context.pcs.get(thread as usize).copied()
+    }
+
+    pub fn set_pc(&mut self, thread: u8, pc: u16) -> Result<(), ClusterError> {
+        let thread_pc = self.context.pcs.get_mut(thread as usize).ok_or(ClusterError::InvalidThread(thread))?;
+        *thread_pc = pc;
+        Ok(())
+    }
+
+    pub fn registers(&self, thread: u8) -> Option<&[u32]> {
+        let start = (thread as usize) * self.nr_of_registers;
+        self.context.registers.get(start..(start + self.nr_of_registers))
+    }
+
+    pub fn registers_mut(&mut self, thread: u8) -> Option<&mut [u32]> {
+        let start = (thread as usize) * self.nr_of_registers;
+        self.context.registers.get_mut(start..(start + self.nr_of_registers))
+    }
+
+    pub fn step(&mut self, thread: u8) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        rank.execute_thread_step_on_dpu(slice, member, thread, &mut self.context)?;
+        Ok(())
+    }
+
+    pub fn read_iram(&self, offset: u16, instructions: &mut [u64]) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        rank.copy_from_iram(slice, member, instructions, offset)?;
+        Ok(())
+    }
+
+    pub fn write_iram(&self, offset: u16, instructions: &[u64]) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        self.driver.forget_resident_programs(&[self.dpu]);
+        rank.copy_to_iram(slice, member, instructions, offset)?;
+        Ok(())
+    }
+
+    pub fn read_wram(&self, offset: u32, data: &mut [u32]) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        rank.copy_from_wram(slice, member, data, offset)?;
+        Ok(())
+    }
+
+    pub fn write_wram(&self, offset: u32, data: &[u32]) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        self.driver.forget_resident_programs(&[self.dpu]);
+        rank.copy_to_wram(slice, member, data, offset)?;
+        Ok(())
+    }
+
+    pub fn read_mram(&self, offset: u32, data: &mut [u8]) -> Result<(), ClusterError> {
+        self.driver.copy_from_memory(&mut FromDpuTransfer::default().bouncing_unaligned_regions().add(self.dpu, offset, data))
+    }
+
+    pub fn write_mram(&self, offset: u32, data: &[u8]) -> Result<(), ClusterError> {
+        self.driver.copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions().add(self.dpu, offset, data))
+    }
+
+    pub fn resume(mut self) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.driver.destructure(&self.dpu);
+        let threads = self.running_threads();
+
+        self.is_finalized = true;
+        rank.finalize_fault_process_for_dpu(slice, member, &mut self.context)?;
+
+        for thread in threads {
+            rank.launch_thread_on_dpu(slice, member, thread, true)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Debugger<'_> {
+    fn drop(&mut self) {
+        if !self.is_finalized {
+            let (rank, slice, member) = self.driver.destructure(&self.dpu);
+            // the error cannot be reported: the DPU will be in fault at the next poll
+            let _ = rank.finalize_fault_process_for_dpu(slice, member, &mut self.context);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use crate::lease::LeasePolicy;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct DpuId {
@@ -7,8 +10,8 @@ pub struct DpuId {
     member: u8
 }
 
-#[derive(Debug)]
-pub struct ProcessId(u64);
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ProcessId(pub u64);
 
 #[derive(Debug)]
 pub struct AllocationInformation {
@@ -31,6 +34,18 @@ impl DpuId {
     }
 }
 
+impl AllocationInformation {
+    pub fn owner(&self) -> &ProcessId {
+        &self.owner
+    }
+}
+
+impl fmt::Display for DpuId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.rank, self.slice, self.member)
+    }
+}
+
 impl Mapping {
     pub fn new(dpus: Vec<DpuId>) -> Self {
         Mapping {
@@ -43,20 +58,79 @@ impl Mapping {
         match self.available.pop() {
             Some(dpu) => {
                 let info = AllocationInformation { owner };
-                self.reserved.insert(dpu.clone(), info);
+                self.reserved.insert(dpu, info);
                 Some(dpu)
             },
             None => None
         }
     }
 
+    pub fn reserve_many(&mut self, owner: ProcessId, nr_of_dpus: usize, policy: LeasePolicy) -> Option<Vec<DpuId>> {
+        if nr_of_dpus > self.available.len() {
+            return None;
+        }
+
+        let dpus = match policy {
+            LeasePolicy::Any => self.available[self.available.len() - nr_of_dpus..].to_vec(),
+            LeasePolicy::PackBySlice => self.pack(nr_of_dpus, |dpu| (dpu.rank, dpu.slice)),
+            LeasePolicy::PackByRank => self.pack(nr_of_dpus, |dpu| (dpu.rank, 0)),
+        };
+
+        let chosen = dpus.iter().collect::<HashSet<_>>();
+        self.available.retain(|dpu| !chosen.contains(dpu));
+
+        for dpu in &dpus {
+            self.reserved.insert(*dpu, AllocationInformation { owner });
+        }
+
+        Some(dpus)
+    }
+
+    pub fn nr_of_available_dpus(&self) -> usize {
+        self.available.len()
+    }
+
+    pub fn owner_of(&self, dpu: &DpuId) -> Option<&ProcessId> {
+        self.reserved.get(dpu).map(|info| &info.owner)
+    }
+
+    pub fn reserved_by(&self, owner: &ProcessId) -> Vec<DpuId> {
+        self.reserved.iter()
+            .filter(|(_, info)| info.owner == *owner)
+            .map(|(dpu, _)| *dpu)
+            .collect()
+    }
+
     pub fn release(&mut self, dpu: &DpuId) -> Option<AllocationInformation> {
         match self.reserved.remove(dpu) {
             Some(info) => {
-                self.available.push(dpu.clone());
+                self.available.push(*dpu);
                 Some(info)
             },
             None => None
         }
     }
-}
\ No newline at end of file
+
+    fn pack<F>(&self, nr_of_dpus: usize, group_of: F) -> Vec<DpuId>
+        where F: Fn(&DpuId) -> (u8, u8)
+    {
+        let mut groups = HashMap::<_, Vec<DpuId>>::default();
+        for dpu in &self.available {
+            groups.entry(group_of(dpu)).or_default().push(*dpu);
+        }
+
+        let mut groups = groups.into_iter().collect::<Vec<_>>();
+        for (_, dpus) in groups.iter_mut() {
+            dpus.sort_by_key(|dpu| dpu.members());
+        }
+
+        // the smallest group that fits the whole request wins, otherwise the request spans the fullest groups
+        match groups.iter().filter(|(_, dpus)| dpus.len() >= nr_of_dpus).min_by_key(|(key, dpus)| (dpus.len(), *key)) {
+            Some((_, dpus)) => dpus[..nr_of_dpus].to_vec(),
+            None => {
+                groups.sort_by_key(|(key, dpus)| (std::cmp::Reverse(dpus.len()), *key));
+                groups.into_iter().flat_map(|(_, dpus)| dpus).take(nr_of_dpus).collect()
+            }
+        }
+    }
+}
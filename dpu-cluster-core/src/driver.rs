@@ -1,20 +1,45 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryInto;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 use crate::dpu::DpuId;
-use dpu_sys::DpuRank;
 use dpu_sys::DpuRankDescription;
-use dpu_sys::DpuRankTransferMatrix;
 use crate::error::ClusterError;
 use crate::program::Program;
+use crate::program::ProgramId;
+use crate::program::SymbolAddress;
 use crate::view::FastSelection;
 use crate::view::Selection;
 use crate::view::View;
 use dpu_sys::DpuDebugContext;
-use crate::memory::MemoryTransfer;
-use crate::memory::MemoryTransferRankEntry;
+use crate::memory::FromDpuTransfer;
+use crate::memory::MRAM_ALIGNMENT;
+use crate::memory::ToDpuTransfer;
+use crate::memory::is_mram_aligned;
+use crate::memory::mram_aligned_range;
 use dpu_sys::DpuTarget;
+use crate::backend::RankBackend;
+use crate::fault::FaultReport;
+use crate::catalog::DataCatalog;
 
 #[derive(Debug)]
 pub struct Driver {
     rank_handler: RankHandler,
+    resident_programs: Mutex<HashMap<DpuId, ProgramId>>,
+    verification: Mutex<Verification>,
+    corrupted_transfers: Mutex<HashMap<DpuId, u64>>,
+    // the fragments overwritten by the transfers of the driver are forgotten
+    catalog: DataCatalog,
+    nr_of_rank_workers: usize,
     pub nr_of_ranks: u8,
     pub target: DpuTarget,
     pub rank_description: DpuRankDescription
@@ -24,35 +49,57 @@ unsafe impl Sync for Driver {}
 
 #[derive(Debug)]
 struct RankHandler {
-    ranks: Vec<DpuRank>
-}
-
-#[derive(Clone)]
-pub enum FaultCause {
-    Breakpoint,
-    Memory,
-    Dma
-}
-
-pub struct FaultInformation {
-    dpu: DpuId,
-    context: DpuDebugContext
+    ranks: Vec<Box<dyn RankBackend>>
 }
 
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
 pub enum RunStatus {
+    #[default]
     Idle,
     Running,
-    Fault(Vec<DpuId>)
+    Fault(Vec<DpuId>),
+    // the DPUs which were still running, and have been stopped
+    TimedOut(Vec<DpuId>)
 }
 
-impl Default for RunStatus {
-    fn default() -> Self {
-        RunStatus::Idle
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
     }
 }
 
+// The default options poll the DPUs without pause until they stop.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub poll_interval: Duration,
+    // when set, the poll interval doubles after each poll, up to this duration
+    pub backoff: Option<Duration>,
+    pub timeout: Option<Duration>,
+    pub cancel_token: Option<CancelToken>
+}
+
+// Verifying a transfer reads the written memory back, which roughly doubles its cost.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Verification {
+    #[default]
+    Disabled,
+    // reports the first corrupted byte, word or instruction
+    Compare,
+    // only reports the start of the corrupted region
+    Checksum
+}
+
 const BOOTSTRAP_THREAD: u8 = 0;
-const PRIMARY_MRAM: u32 = 0;
+const WRAM_WORD_SIZE: u32 = 4;
+// the IRAM does not keep the upper bits of the 48-bit instructions
+const INSTRUCTION_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
 
 trait FromRankId<'a> {
     fn from_rank_id(rank_id: u8, handler: &'a RankHandler) -> Self;
@@ -64,7 +111,7 @@ impl <'a> FromRankId<'a> for u8 {
     }
 }
 
-impl <'a> FromRankId<'a> for &'a DpuRank {
+impl <'a> FromRankId<'a> for &'a dyn RankBackend {
     fn from_rank_id(rank_id: u8, handler: &'a RankHandler) -> Self {
         handler.get_rank(rank_id)
     }
@@ -75,37 +122,72 @@ pub trait Mergeable {
 }
 
 impl Mergeable for () {
-    fn merge_with(&self, _: &Self) -> Self {
-        ()
+    fn merge_with(&self, _: &Self) -> Self {}
+}
+
+impl Mergeable for Vec<DpuId> {
+    fn merge_with(&self, other: &Self) -> Self {
+        let mut all = self.to_vec();
+        all.extend_from_slice(other);
+        all
     }
 }
 
 impl Mergeable for RunStatus {
     fn merge_with(&self, other: &Self) -> Self {
+        // faults prevail over timeouts, which prevail over running DPUs
         match (self, other) {
+            (RunStatus::Fault(faults), RunStatus::Fault(other_faults)) => RunStatus::Fault(faults.merge_with(other_faults)),
+            (RunStatus::Fault(faults), _) | (_, RunStatus::Fault(faults)) => RunStatus::Fault(faults.to_vec()),
+            (RunStatus::TimedOut(dpus), RunStatus::TimedOut(other_dpus)) => RunStatus::TimedOut(dpus.merge_with(other_dpus)),
+            (RunStatus::TimedOut(dpus), _) | (_, RunStatus::TimedOut(dpus)) => RunStatus::TimedOut(dpus.to_vec()),
+            (RunStatus::Running, _) | (_, RunStatus::Running) => RunStatus::Running,
             (RunStatus::Idle, RunStatus::Idle) => RunStatus::Idle,
-            (RunStatus::Idle, RunStatus::Running) => RunStatus::Running,
-            (RunStatus::Idle, RunStatus::Fault(other_faults)) => RunStatus::Fault(other_faults.to_vec()),
-            (RunStatus::Running, RunStatus::Idle) => RunStatus::Running,
-            (RunStatus::Running, RunStatus::Running) => RunStatus::Running,
-            (RunStatus::Running, RunStatus::Fault(other_faults)) => RunStatus::Fault(other_faults.to_vec()),
-            (RunStatus::Fault(faults), RunStatus::Idle) => RunStatus::Fault(faults.to_vec()),
-            (RunStatus::Fault(faults), RunStatus::Running) => RunStatus::Fault(faults.to_vec()),
-            (RunStatus::Fault(faults), RunStatus::Fault(other_faults)) => {
-                let mut all_faults = faults.to_vec();
-                all_faults.append(&mut other_faults.to_vec());
-                RunStatus::Fault(all_faults)
-            },
         }
     }
 }
 
 impl Driver {
-    pub fn new(ranks: Vec<DpuRank>, rank_description: DpuRankDescription, target: DpuTarget) -> Self {
+    pub fn new(ranks: Vec<Box<dyn RankBackend>>, rank_description: DpuRankDescription, target: DpuTarget) -> Self {
         let nr_of_ranks = ranks.len() as u8;
         let rank_handler = RankHandler { ranks };
 
-        Driver { rank_handler, nr_of_ranks, rank_description, target }
+        Driver {
+            rank_handler,
+            resident_programs: Default::default(),
+            verification: Default::default(),
+            corrupted_transfers: Default::default(),
+            catalog: Default::default(),
+            nr_of_rank_workers: 1,
+            nr_of_ranks,
+            rank_description,
+            target
+        }
+    }
+
+    // the operations on several ranks are split between this many threads, 1 keeping them on the calling thread
+    pub fn with_rank_workers(mut self, nr_of_rank_workers: usize) -> Self {
+        self.nr_of_rank_workers = nr_of_rank_workers.max(1);
+        self
+    }
+
+    pub fn nr_of_rank_workers(&self) -> usize {
+        self.nr_of_rank_workers
+    }
+
+    pub fn verification(&self) -> Verification {
+        // unwrap: the lock is never held across a panicking operation
+        *self.verification.lock().unwrap()
+    }
+
+    // applies to the following memory transfers and program loads
+    pub fn set_verification(&self, verification: Verification) {
+        *self.verification.lock().unwrap() = verification;
+    }
+
+    // the number of corrupted regions detected on each DPU since the creation of the driver
+    pub fn corrupted_transfers(&self) -> HashMap<DpuId, u64> {
+        self.corrupted_transfers.lock().unwrap().clone()
     }
 
     pub fn nr_of_dpus(&self) -> usize {
@@ -115,12 +197,28 @@ impl Driver {
     }
 
     pub fn load(&self, view: &View, program: &Program) -> Result<(), ClusterError> {
+        program.validate(&self.rank_description)?;
+
         self.dispatch(view,
                       |dpu| self.load_dpu(dpu, program),
-                      |rank| self.load_rank(rank, program),
+                      |rank_id| self.load_rank(rank_id, program),
                       || self.load_all(program))
     }
 
+    pub fn load_if_needed(&self, dpu: &DpuId, program: &Program) -> Result<bool, ClusterError> {
+        if self.resident_program(dpu) == Some(program.id()) {
+            return Ok(false);
+        }
+
+        self.load(&View::one(*dpu), program)?;
+        Ok(true)
+    }
+
+    pub fn resident_program(&self, dpu: &DpuId) -> Option<ProgramId> {
+        // unwrap: the lock is never held across a panicking operation
+        self.resident_programs.lock().unwrap().get(dpu).copied()
+    }
+
     pub fn boot(&self, view: &View) -> Result<(), ClusterError> {
         self.dispatch(view,
                       |dpu| self.boot_dpu(dpu),
@@ -136,6 +234,14 @@ impl Driver {
     }
 
     pub fn run(&self, view: &View) -> Result<RunStatus, ClusterError> {
+        self.run_with(view, RunOptions::default())
+    }
+
+    // On timeout or cancellation, the DPUs still running are stopped by a fault.
+    pub fn run_with(&self, view: &View, options: RunOptions) -> Result<RunStatus, ClusterError> {
+        let start = Instant::now();
+        let mut poll_interval = options.poll_interval;
+
         self.boot(view)?;
 
         loop {
@@ -143,37 +249,161 @@ impl Driver {
                 RunStatus::Running => (),
                 finished => return Ok(finished),
             }
+
+            if options.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled) {
+                return Err(ClusterError::RunCancelled(self.stop_running_dpus(view)?));
+            }
+
+            let remaining = match options.timeout {
+                None => poll_interval,
+                Some(timeout) => match timeout.checked_sub(start.elapsed()) {
+                    None => return Ok(RunStatus::TimedOut(self.stop_running_dpus(view)?)),
+                    Some(remaining) => remaining,
+                },
+            };
+
+            if !poll_interval.is_zero() {
+                thread::sleep(poll_interval.min(remaining));
+            }
+
+            if let Some(max_poll_interval) = options.backoff {
+                // a null interval would never grow
+                poll_interval = poll_interval.saturating_mul(2).max(Duration::from_millis(1)).min(max_poll_interval);
+            }
         }
     }
 
-    pub fn copy_to_memory(&self, data: &mut MemoryTransfer<'_>) -> Result<(), ClusterError> {
-        for (rank_id, rank_transfers) in data.0.iter_mut() {
-            let rank= self.rank_handler.get_rank(*rank_id);
-            let matrix = self.create_transfer_matrix_for(rank, rank_transfers)?;
-            rank.copy_to_mrams(&matrix)?;
+    pub fn catalog(&self) -> &DataCatalog {
+        &self.catalog
+    }
+
+    pub fn copy_to_memory(&self, data: &mut ToDpuTransfer<'_>) -> Result<(), ClusterError> {
+        data.check_bounds(self.rank_description.memories.mram_size)?;
+        for (dpu, _, entry) in data.entries() {
+            self.catalog.forget_overlapping(dpu, entry.offset, entry.reference.len() as u32);
+        }
+        if data.has_unaligned_regions()? {
+            return self.bounce_to_memory(data);
         }
 
-        Ok(())
+        self.fan_out(data.ranks.iter_mut().collect(), |(rank_id, rank_transfers)| {
+            let rank = self.rank_handler.get_rank(*rank_id);
+            Ok(rank.copy_to_mrams(rank_transfers)?)
+        })?;
+
+        self.verify_mram_transfer(data)
     }
 
-    pub fn copy_from_memory(&self, data: &mut MemoryTransfer<'_>) -> Result<(), ClusterError> {
-        for (rank_id, rank_transfers) in data.0.iter_mut() {
-            let rank= self.rank_handler.get_rank(*rank_id);
-            let matrix = self.create_transfer_matrix_for(rank, rank_transfers)?;
-            rank.copy_from_mrams(&matrix)?;
+    pub fn copy_from_memory(&self, data: &mut FromDpuTransfer<'_>) -> Result<(), ClusterError> {
+        data.check_bounds(self.rank_description.memories.mram_size)?;
+        if data.has_unaligned_regions()? {
+            return self.bounce_from_memory(data);
         }
 
-        Ok(())
+        self.fan_out(data.ranks.iter_mut().collect(), |(rank_id, rank_transfers)| {
+            let rank = self.rank_handler.get_rank(*rank_id);
+            Ok(rank.copy_from_mrams(rank_transfers)?)
+        })
+    }
+
+    // Every entry of the transfer matrices points to the same buffer, unless the region is unaligned:
+    // it is then bounced through a buffer per DPU.
+    pub fn broadcast_to_memory(&self, view: &View, offset: u32, data: &[u8]) -> Result<(), ClusterError> {
+        let mut transfer = ToDpuTransfer::default().bouncing_unaligned_regions();
+
+        for dpu in self.dpus_in(view)? {
+            transfer.add_in_place(dpu, offset, data);
+        }
+
+        self.copy_to_memory(&mut transfer)
+    }
+
+    pub fn copy_to_symbol(&self, dpu: &DpuId, program: &Program, symbol: &str, offset: u32, data: &[u8]) -> Result<(), ClusterError> {
+        let (rank, slice_id, member_id) = self.destructure(dpu);
+
+        match program.resolve(symbol, offset, data.len() as u32)? {
+            SymbolAddress::Mram(address) => self.copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions().add(*dpu, address, data)),
+            SymbolAddress::Wram(address) => {
+                // the WRAM is only accessible by words: partially written words are read first
+                let (first_word, mut words) = wram_words_for(address, data.len());
+                if !address.is_multiple_of(WRAM_WORD_SIZE) || !data.len().is_multiple_of(WRAM_WORD_SIZE as usize) {
+                    rank.copy_from_wram(slice_id, member_id, &mut words, first_word)?;
+                }
+
+                let mut bytes = words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<_>>();
+                let start = (address % WRAM_WORD_SIZE) as usize;
+                bytes[start..start + data.len()].copy_from_slice(data);
+
+                for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(WRAM_WORD_SIZE as usize)) {
+                    // unwrap: the chunks have the size of a word
+                    *word = u32::from_le_bytes(chunk.try_into().unwrap());
+                }
+
+                Ok(rank.copy_to_wram(slice_id, member_id, &words, first_word)?)
+            },
+            SymbolAddress::Iram(_) => Err(ClusterError::InvalidSymbolLocation(symbol.to_string())),
+        }
+    }
+
+    pub fn copy_from_symbol(&self, dpu: &DpuId, program: &Program, symbol: &str, offset: u32, data: &mut [u8]) -> Result<(), ClusterError> {
+        let (rank, slice_id, member_id) = self.destructure(dpu);
+
+        match program.resolve(symbol, offset, data.len() as u32)? {
+            SymbolAddress::Mram(address) => self.copy_from_memory(&mut FromDpuTransfer::default().bouncing_unaligned_regions().add(*dpu, address, data)),
+            SymbolAddress::Wram(address) => {
+                let (first_word, mut words) = wram_words_for(address, data.len());
+                rank.copy_from_wram(slice_id, member_id, &mut words, first_word)?;
+
+                let bytes = words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<_>>();
+                let start = (address % WRAM_WORD_SIZE) as usize;
+                data.copy_from_slice(&bytes[start..start + data.len()]);
+
+                Ok(())
+            },
+            SymbolAddress::Iram(_) => Err(ClusterError::InvalidSymbolLocation(symbol.to_string())),
+        }
+    }
+
+    // DPUs which finished or faulted meanwhile are left as they are
+    fn stop_running_dpus(&self, view: &View) -> Result<Vec<DpuId>, ClusterError> {
+        let mut dpus_by_rank = BTreeMap::<u8, Vec<DpuId>>::new();
+        for dpu in self.dpus_in(view)? {
+            dpus_by_rank.entry(dpu.members().0).or_default().push(dpu);
+        }
+
+        let mut stopped = Vec::default();
+
+        for (rank_id, dpus) in dpus_by_rank {
+            let rank = self.rank_handler.get_rank(rank_id);
+            let (is_running, is_in_fault) = rank.poll_all()?;
+
+            let running = dpus.into_iter()
+                .filter(|dpu| {
+                    let (_, slice_id, member_id) = dpu.members();
+                    is_running.is_set(slice_id, member_id) && !is_in_fault.is_set(slice_id, member_id)
+                })
+                .collect::<Vec<_>>();
+
+            if running.len() == self.dpus_of_rank(rank_id).len() {
+                rank.fault_all()?;
+            } else {
+                for dpu in &running {
+                    let (_, slice_id, member_id) = dpu.members();
+                    rank.fault_dpu(slice_id, member_id)?;
+                }
+            }
+
+            stopped.extend(running);
+        }
+
+        Ok(stopped)
     }
 
-    pub fn fetch_dpu_fault_context(&self, dpu: &DpuId) -> Result<FaultInformation, ClusterError> {
+    pub fn fetch_dpu_fault_context(&self, dpu: &DpuId) -> Result<FaultReport, ClusterError> {
         let (rank, slice_id, member) = self.destructure(dpu);
-        let mut context =
-            DpuDebugContext::new(self.rank_description.info.nr_of_threads,
-                                 self.rank_description.info.nr_of_work_registers_per_thread,
-                                 self.rank_description.info.nr_of_atomic_bits);
+        let mut context = self.new_debug_context();
         rank.initialize_fault_process_for_dpu(slice_id, member, &mut context)?;
-        Ok(FaultInformation { dpu: dpu.clone(), context })
+        Ok(FaultReport::new(*dpu, &context))
     }
 
     fn dispatch<'a, T, FnRankArg, FnDpu, FnRank, FnAll>(&'a self, view: &View, for_dpu: FnDpu, for_rank: FnRank, for_all: FnAll) -> Result<T, ClusterError>
@@ -235,21 +465,75 @@ impl Driver {
         Ok(result)
     }
 
-    fn load_all(&self, program: &Program) -> Result<(), ClusterError> {
-        for rank in &self.rank_handler.ranks {
-            self.load_rank(rank, program)?;
+    // The items are split in contiguous chunks, one per worker, so that the results are merged in the order of the items.
+    // The first error, in that order, is returned.
+    fn fan_out<I, T, F>(&self, items: Vec<I>, f: F) -> Result<T, ClusterError>
+        where I: Send,
+              T: Default + Mergeable + Send,
+              F: Fn(I) -> Result<T, ClusterError> + Sync
+    {
+        let fold = |chunk: Vec<I>| chunk.into_iter()
+            .try_fold(T::default(), |result, item| f(item).map(|item_result| result.merge_with(&item_result)));
+
+        if self.nr_of_rank_workers <= 1 || items.len() <= 1 {
+            return fold(items);
         }
 
-        Ok(())
+        let chunk_size = items.len().div_ceil(self.nr_of_rank_workers);
+        let mut chunks = Vec::default();
+        let mut items = items.into_iter().peekable();
+        while items.peek().is_some() {
+            chunks.push(items.by_ref().take(chunk_size).collect::<Vec<_>>());
+        }
+
+        thread::scope(|scope| {
+            let workers = chunks.into_iter()
+                .map(|chunk| scope.spawn(|| fold(chunk)))
+                .collect::<Vec<_>>();
+
+            workers.into_iter().try_fold(T::default(), |result, worker| {
+                // unwrap: a panicking worker panics the caller as well
+                let chunk_result = worker.join().unwrap()?;
+                Ok(result.merge_with(&chunk_result))
+            })
+        })
+    }
+
+    fn dpus_in(&self, view: &View) -> Result<Vec<DpuId>, ClusterError> {
+        self.dispatch(view,
+                      |dpu| Ok(vec![*dpu]),
+                      |rank_id| Ok(self.dpus_of_rank(rank_id)),
+                      || Ok((0..self.nr_of_ranks).flat_map(|rank_id| self.dpus_of_rank(rank_id)).collect()))
+    }
+
+    fn dpus_of_rank(&self, rank_id: u8) -> Vec<DpuId> {
+        let topology = &self.rank_description.topology;
+
+        (0..topology.nr_of_control_interfaces)
+            .flat_map(|slice| (0..topology.nr_of_dpus_per_control_interface).map(move |member| DpuId::new(rank_id, slice, member)))
+            .collect()
     }
 
-    fn load_rank(&self, rank: &DpuRank, program: &Program) -> Result<(), ClusterError> {
-        for (offset, instructions) in &program.iram_sections {
-            rank.copy_to_irams(instructions.as_ptr(), instructions.len() as u16, *offset)?;
+    fn load_all(&self, program: &Program) -> Result<(), ClusterError> {
+        self.fan_out((0..self.nr_of_ranks).collect(), |rank_id| self.load_rank(rank_id, program))
+    }
+
+    fn load_rank(&self, rank_id: u8, program: &Program) -> Result<(), ClusterError> {
+        let rank = self.rank_handler.get_rank(rank_id);
+        let dpus = self.dpus_of_rank(rank_id);
+
+        self.forget_resident_programs(&dpus);
+
+        for (offset, instructions) in program.iram_sections() {
+            rank.copy_to_irams(instructions, *offset)?;
         }
-        for (offset, data) in &program.wram_sections {
-            rank.copy_to_wrams(data.as_ptr(), data.len() as u32, *offset)?;
+        for (offset, data) in program.wram_sections() {
+            rank.copy_to_wrams(data, *offset)?;
         }
+        self.verify_program_sections(&dpus, program)?;
+        self.copy_mram_sections(&dpus, program)?;
+
+        self.set_resident_program(&dpus, program.id());
 
         Ok(())
     }
@@ -257,98 +541,280 @@ impl Driver {
     fn load_dpu(&self, dpu: &DpuId, program: &Program) -> Result<(), ClusterError> {
         let (rank, slice, member) = self.destructure(dpu);
 
-        for (offset, instructions) in &program.iram_sections {
-            rank.copy_to_iram(slice, member, instructions.as_ptr(), instructions.len() as u16, *offset)?;
+        self.forget_resident_programs(&[*dpu]);
+
+        for (offset, instructions) in program.iram_sections() {
+            rank.copy_to_iram(slice, member, instructions, *offset)?;
         }
-        for (offset, data) in &program.wram_sections {
-            rank.copy_to_wram(slice, member, data.as_ptr(), data.len() as u32, *offset)?;
+        for (offset, data) in program.wram_sections() {
+            rank.copy_to_wram(slice, member, data, *offset)?;
         }
+        self.verify_program_sections(&[*dpu], program)?;
+        self.copy_mram_sections(&[*dpu], program)?;
+
+        self.set_resident_program(&[*dpu], program.id());
 
         Ok(())
     }
 
-    fn boot_all(&self) -> Result<(), ClusterError> {
-        for rank in &self.rank_handler.ranks {
-            self.boot_rank(rank)?;
+    fn copy_mram_sections(&self, dpus: &[DpuId], program: &Program) -> Result<(), ClusterError> {
+        let mut transfer = ToDpuTransfer::default().bouncing_unaligned_regions();
+
+        for dpu in dpus {
+            for (offset, data) in program.mram_sections() {
+                transfer.add_in_place(*dpu, *offset, data.as_slice());
+            }
         }
 
-        Ok(())
+        self.copy_to_memory(&mut transfer)
     }
 
-    fn boot_rank(&self, rank: &DpuRank) -> Result<(), ClusterError> {
-        let nr_of_slices = self.rank_description.topology.nr_of_control_interfaces as usize;
-        let mut was_running = vec!(0; nr_of_slices);
+    fn bounce_to_memory(&self, data: &ToDpuTransfer<'_>) -> Result<(), ClusterError> {
+        let mut unaligned_regions = HashMap::<DpuId, Vec<(usize, u32, &[u8])>>::new();
 
-        rank.launch_thread_on_all(BOOTSTRAP_THREAD, false, was_running.as_mut_ptr())?;
+        for (dpu, region, entry) in data.entries() {
+            if !is_mram_aligned(entry.offset, entry.reference.len()) {
+                unaligned_regions.entry(*dpu).or_default().push((region, entry.offset, entry.reference));
+            }
+        }
 
-        for slice_was_running in was_running {
-            if slice_was_running != 0 {
-                return Err(ClusterError::DpuIsAlreadyRunning);
+        // the regions sharing an aligned block are bounced together, in place of the first of them
+        let mut bounces = HashMap::new();
+        for (dpu, mut regions) in unaligned_regions {
+            regions.sort_by_key(|(_, offset, _)| *offset);
+
+            let mut groups: Vec<Vec<(usize, u32, &[u8])>> = Vec::default();
+            let mut group_end = 0;
+            for region in regions {
+                let (start, end) = mram_aligned_range(region.1, region.2.len());
+                match groups.last_mut() {
+                    Some(group) if start < group_end => {
+                        group.push(region);
+                        group_end = group_end.max(end);
+                    },
+                    _ => {
+                        groups.push(vec![region]);
+                        group_end = end;
+                    },
+                }
+            }
+
+            for mut group in groups {
+                // the overlapping bytes are written in the order of the regions
+                group.sort_by_key(|(region, _, _)| *region);
+                bounces.insert((dpu, group[0].0), Some(self.bounce_buffer_for(&dpu, &group)?));
+                for (region, _, _) in &group[1..] {
+                    bounces.insert((dpu, *region), None);
+                }
             }
         }
 
-        Ok(())
-    }
+        let mut transfer = ToDpuTransfer::default();
+        for (dpu, region, entry) in data.entries() {
+            match bounces.get(&(*dpu, region)) {
+                None => transfer.add_in_place(*dpu, entry.offset, entry.reference),
+                Some(Some((start, buffer))) => transfer.add_in_place(*dpu, *start, buffer.as_slice()),
+                Some(None) => (),
+            }
+        }
 
-    fn boot_dpu(&self, dpu: &DpuId) -> Result<(), ClusterError> {
-        let mut was_running = false;
-        let (rank, slice, member) = self.destructure(dpu);
+        self.copy_to_memory(&mut transfer)
+    }
 
-        rank.launch_thread_on_dpu(slice, member, BOOTSTRAP_THREAD, false, &mut was_running)?;
+    // the blocks holding an unaligned end of a region are read first, for their other bytes to be written back as they were
+    fn bounce_buffer_for(&self, dpu: &DpuId, regions: &[(usize, u32, &[u8])]) -> Result<(u32, Vec<u8>), ClusterError> {
+        let (rank, slice_id, member_id) = self.destructure(dpu);
+        let ranges = regions.iter().map(|(_, offset, data)| mram_aligned_range(*offset, data.len()));
+        // unwrap: a bounce buffer is created for at least one region
+        let start = ranges.clone().map(|(start, _)| start).min().unwrap();
+        let end = ranges.map(|(_, end)| end).max().unwrap();
+        let mut buffer = vec![0u8; (end - start) as usize];
+        let block = MRAM_ALIGNMENT as usize;
+
+        let mut partial_blocks = regions.iter()
+            .flat_map(|(_, offset, data)| vec![*offset, *offset + data.len() as u32])
+            .filter(|boundary| !boundary.is_multiple_of(MRAM_ALIGNMENT))
+            .map(|boundary| boundary - boundary % MRAM_ALIGNMENT)
+            .collect::<Vec<_>>();
+        partial_blocks.sort_unstable();
+        partial_blocks.dedup();
+
+        for block_start in partial_blocks {
+            let position = (block_start - start) as usize;
+            rank.copy_from_mram(slice_id, member_id, &mut buffer[position..position + block], block_start)?;
+        }
 
-        if was_running {
-            Err(ClusterError::DpuIsAlreadyRunning)
-        } else {
-            Ok(())
+        for (_, offset, data) in regions {
+            let position = (offset - start) as usize;
+            buffer[position..position + data.len()].copy_from_slice(data);
         }
+
+        Ok((start, buffer))
     }
 
-    fn fetch_all_status(&self) -> Result<RunStatus, ClusterError> {
-        let nr_of_ranks = self.nr_of_ranks;
-        let mut status = RunStatus::default();
+    fn bounce_from_memory(&self, data: &mut FromDpuTransfer<'_>) -> Result<(), ClusterError> {
+        let mut bounces = HashMap::new();
+        let mut transfer = FromDpuTransfer::default();
+
+        for (rank_id, rank_transfers) in data.ranks.iter_mut() {
+            for (dpu, entries) in rank_transfers.0.iter_mut() {
+                for (region, entry) in entries.iter_mut().enumerate() {
+                    if is_mram_aligned(entry.offset, entry.reference.len()) {
+                        transfer.add_in_place(*dpu, entry.offset, &mut *entry.reference);
+                    } else {
+                        let (start, end) = mram_aligned_range(entry.offset, entry.reference.len());
+                        bounces.insert((*rank_id, *dpu, region), (start, vec![0u8; (end - start) as usize]));
+                    }
+                }
+            }
+        }
 
-        for rank_id in 0..nr_of_ranks {
-            let rank_status = self.fetch_rank_status(rank_id)?;
-            status = status.merge_with(&rank_status);
+        for ((_, dpu, _), (start, buffer)) in bounces.iter_mut() {
+            transfer.add_in_place(*dpu, *start, buffer.as_mut_slice());
         }
 
-        Ok(status)
+        self.copy_from_memory(&mut transfer)?;
+
+        for ((rank_id, dpu, region), (start, buffer)) in bounces {
+            // unwrap: the bounce buffers were created from these entries
+            let entry = data.ranks.get_mut(&rank_id).and_then(|rank| rank.0.get_mut(&dpu)).and_then(|entries| entries.get_mut(region)).unwrap();
+            let position = (entry.offset - start) as usize;
+            let length = entry.reference.len();
+            entry.reference.copy_from_slice(&buffer[position..position + length]);
+        }
+
+        Ok(())
     }
 
-    fn fetch_rank_status(&self, rank_id: u8) -> Result<RunStatus, ClusterError> {
-        let mut running = false;
-        let mut fault = false;
-        let mut faults = Vec::default();
-        let nr_of_control_interfaces_per_rank = self.rank_description.topology.nr_of_control_interfaces as usize;
-        let nr_of_dpus_per_control_interface = self.rank_description.topology.nr_of_dpus_per_control_interface;
+    fn verify_mram_transfer(&self, data: &ToDpuTransfer<'_>) -> Result<(), ClusterError> {
+        let verification = self.verification();
+        if verification == Verification::Disabled {
+            return Ok(());
+        }
 
-        let rank = self.rank_handler.get_rank(rank_id);
-        let mut run_bitfields = vec![0; nr_of_control_interfaces_per_rank];
-        let mut fault_bitfields = vec![0; nr_of_control_interfaces_per_rank];
+        let mut corruptions = Vec::default();
 
-        rank.poll_all(run_bitfields.as_mut_ptr(), fault_bitfields.as_mut_ptr())?;
+        for (dpu, _, entry) in data.entries() {
+            let (rank, slice_id, member_id) = self.destructure(dpu);
+            let mut written = vec![0u8; entry.reference.len()];
+            rank.copy_from_mram(slice_id, member_id, &mut written, entry.offset)?;
 
-        for slice_id in 0..nr_of_control_interfaces_per_rank {
-            if run_bitfields[slice_id] == 0 {
-                continue;
+            if let Some(position) = find_corruption(verification, entry.reference, &written) {
+                corruptions.push((*dpu, entry.offset + position as u32));
             }
+        }
 
-            running = true;
-            let fault_bitfield = fault_bitfields[slice_id];
-            if fault_bitfield != 0 {
-                fault = true;
+        self.report_corruptions(corruptions)
+    }
 
-                for member in 0..nr_of_dpus_per_control_interface {
-                    if (fault_bitfield & (1 << (member as u32))) != 0 {
-                        faults.push(DpuId::new(rank_id, slice_id as u8, member));
-                    }
+    fn verify_program_sections(&self, dpus: &[DpuId], program: &Program) -> Result<(), ClusterError> {
+        let verification = self.verification();
+        if verification == Verification::Disabled {
+            return Ok(());
+        }
+
+        let mut corruptions = Vec::default();
+
+        for dpu in dpus {
+            let (rank, slice_id, member_id) = self.destructure(dpu);
+
+            for (offset, instructions) in program.iram_sections() {
+                let expected = instructions.iter().map(|instruction| instruction & INSTRUCTION_MASK).collect::<Vec<_>>();
+                let mut written = vec![0u64; instructions.len()];
+                rank.copy_from_iram(slice_id, member_id, &mut written, *offset)?;
+
+                if let Some(position) = find_corruption(verification, &expected, &written) {
+                    corruptions.push((*dpu, *offset as u32 + position as u32));
+                }
+            }
+            for (offset, data) in program.wram_sections() {
+                let mut written = vec![0u32; data.len()];
+                rank.copy_from_wram(slice_id, member_id, &mut written, *offset)?;
+
+                if let Some(position) = find_corruption(verification, data, &written) {
+                    corruptions.push((*dpu, *offset + position as u32));
                 }
             }
         }
 
-        if !running {
+        self.report_corruptions(corruptions)
+    }
+
+    // every corruption is counted, but only the first one is returned
+    fn report_corruptions(&self, corruptions: Vec<(DpuId, u32)>) -> Result<(), ClusterError> {
+        let mut corrupted_transfers = self.corrupted_transfers.lock().unwrap();
+
+        for (dpu, _) in &corruptions {
+            *corrupted_transfers.entry(*dpu).or_insert(0) += 1;
+        }
+
+        match corruptions.first() {
+            None => Ok(()),
+            Some((dpu, offset)) => Err(ClusterError::CorruptedTransfer { dpu: *dpu, offset: *offset }),
+        }
+    }
+
+    // a partially loaded program must not be seen as resident
+    pub(crate) fn forget_resident_programs(&self, dpus: &[DpuId]) {
+        let mut resident_programs = self.resident_programs.lock().unwrap();
+
+        for dpu in dpus {
+            resident_programs.remove(dpu);
+        }
+    }
+
+    fn set_resident_program(&self, dpus: &[DpuId], program: ProgramId) {
+        let mut resident_programs = self.resident_programs.lock().unwrap();
+
+        for dpu in dpus {
+            resident_programs.insert(*dpu, program);
+        }
+    }
+
+    fn boot_all(&self) -> Result<(), ClusterError> {
+        self.fan_out(self.rank_handler.ranks.iter().collect(), |rank| self.boot_rank(rank.as_ref()))
+    }
+
+    fn boot_rank(&self, rank: &dyn RankBackend) -> Result<(), ClusterError> {
+        let was_running = rank.launch_thread_on_all(BOOTSTRAP_THREAD, false)?;
+
+        if was_running.is_empty() {
+            Ok(())
+        } else {
+            Err(ClusterError::DpuIsAlreadyRunning)
+        }
+    }
+
+    fn boot_dpu(&self, dpu: &DpuId) -> Result<(), ClusterError> {
+        let (rank, slice, member) = self.destructure(dpu);
+
+        let was_running = rank.launch_thread_on_dpu(slice, member, BOOTSTRAP_THREAD, false)?;
+
+        if was_running {
+            Err(ClusterError::DpuIsAlreadyRunning)
+        } else {
+            Ok(())
+        }
+    }
+
+    // polled in a loop by the runs: cheaper on the calling thread than spread on workers
+    fn fetch_all_status(&self) -> Result<RunStatus, ClusterError> {
+        (0..self.nr_of_ranks).try_fold(RunStatus::default(), |status, rank_id| {
+            self.fetch_rank_status(rank_id).map(|rank_status| status.merge_with(&rank_status))
+        })
+    }
+
+    fn fetch_rank_status(&self, rank_id: u8) -> Result<RunStatus, ClusterError> {
+        let rank = self.rank_handler.get_rank(rank_id);
+        let (is_running, is_in_fault) = rank.poll_all()?;
+
+        let faults = is_in_fault.iter()
+            .map(|(slice_id, member_id)| DpuId::new(rank_id, slice_id, member_id))
+            .collect::<Vec<_>>();
+
+        if is_running.is_empty() {
             Ok(RunStatus::Idle)
-        } else if !fault {
+        } else if faults.is_empty() {
             Ok(RunStatus::Running)
         } else {
             Ok(RunStatus::Fault(faults))
@@ -358,35 +824,24 @@ impl Driver {
     fn fetch_dpu_status(&self, dpu: &DpuId) -> Result<RunStatus, ClusterError> {
         let (rank, slice, member) = self.destructure(dpu);
 
-        let mut running = false;
-        let mut fault = false;
-
-        rank.poll_dpu(slice, member, &mut running, &mut fault)?;
+        let (running, fault) = rank.poll_dpu(slice, member)?;
 
         if !running {
             Ok(RunStatus::Idle)
         } else if !fault {
             Ok(RunStatus::Running)
         } else {
-            Ok(RunStatus::Fault(vec![dpu.clone()]))
+            Ok(RunStatus::Fault(vec![*dpu]))
         }
     }
 
-    fn create_transfer_matrix_for<'a>(&self, rank: &'a DpuRank, data: &mut MemoryTransferRankEntry<'_>) -> Result<DpuRankTransferMatrix<'a>, ClusterError> {
-        let matrix = DpuRankTransferMatrix::allocate_for(rank)?;
-
-        for (dpu, image) in data.0.iter_mut() {
-            let (_, slice, member) = dpu.members();
-            let offset = image.offset;
-            let length = image.reference.len() as u32;
-
-            matrix.add_dpu(slice, member, image.ptr(), length, offset, PRIMARY_MRAM);
-        }
-
-        Ok(matrix)
+    pub(crate) fn new_debug_context(&self) -> DpuDebugContext {
+        DpuDebugContext::new(self.rank_description.info.nr_of_threads,
+                             self.rank_description.info.nr_of_work_registers_per_thread,
+                             self.rank_description.info.nr_of_atomic_bits)
     }
 
-    fn destructure(&self, dpu: &DpuId) -> (&DpuRank, u8, u8) {
+    pub(crate) fn destructure(&self, dpu: &DpuId) -> (&dyn RankBackend, u8, u8) {
         let (rank_id, slice_id, member_id) = dpu.members();
         let rank = self.rank_handler.get_rank(rank_id);
 
@@ -395,8 +850,30 @@ impl Driver {
 }
 
 impl RankHandler {
-    fn get_rank(&self, rank_id: u8) -> &DpuRank {
+    fn get_rank(&self, rank_id: u8) -> &dyn RankBackend {
         // unwrap: DpuId are checked during their creation
-        self.ranks.get(rank_id as usize).unwrap()
+        self.ranks.get(rank_id as usize).unwrap().as_ref()
+    }
+}
+
+// the position of the first corrupted value, relative to the start of the region
+fn find_corruption<T: Eq + Hash>(verification: Verification, expected: &[T], written: &[T]) -> Option<usize> {
+    match verification {
+        Verification::Disabled => None,
+        Verification::Compare => expected.iter().zip(written).position(|(expected, written)| expected != written),
+        Verification::Checksum => if checksum(expected) == checksum(written) { None } else { Some(0) },
     }
-}
\ No newline at end of file
+}
+
+fn checksum<T: Hash>(values: &[T]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    values.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn wram_words_for(address: u32, length: usize) -> (u32, Vec<u32>) {
+    let first_word = address / WRAM_WORD_SIZE;
+    let last_word = (address + length as u32).div_ceil(WRAM_WORD_SIZE);
+
+    (first_word, vec![0; (last_word - first_word) as usize])
+}
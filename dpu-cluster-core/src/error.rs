@@ -1,12 +1,31 @@
+use std::io;
 use dpu_sys::DpuError;
 use crate::dpu::DpuId;
+use crate::program::ProgramError;
 
 #[derive(Debug, Clone)]
 pub enum ClusterError {
     NotEnoughResources {expected: u32, found: u32 },
     LowLevelError(DpuError),
     DpuIsAlreadyRunning,
-    DpuIsInFault(DpuId)
+    DpuIsInFault(DpuId),
+    RankBusy,
+    LockFailure(io::ErrorKind),
+    UnknownSymbol(String),
+    InvalidSymbolLocation(String),
+    SymbolOverflow { symbol: String, offset: u32, length: u32, size: u32 },
+    InvalidProgram(ProgramError),
+    UnalignedMramAccess { dpu: DpuId, offset: u32, length: u32 },
+    OutOfBounds { dpu: DpuId, offset: u32, length: u32, limit: u32 },
+    RegionAlreadyAllocated { dpu: DpuId, region: String },
+    UnknownRegion { dpu: DpuId, region: String },
+    NotEnoughMram { dpu: DpuId, requested: u32, largest_free_block: u32 },
+    RegionOverflow { region: String, length: u32, size: u32 },
+    DatasetTypeMismatch(String),
+    CorruptedTransfer { dpu: DpuId, offset: u32 },
+    // the DPUs which were still running, and have been stopped
+    RunCancelled(Vec<DpuId>),
+    InvalidThread(u8)
 }
 
 impl From<DpuError> for ClusterError {
@@ -0,0 +1,105 @@
+use std::fmt;
+use dpu_sys::DpuDebugContext;
+use crate::dpu::DpuId;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FaultCause {
+    Breakpoint,
+    Memory,
+    Dma,
+    Unknown
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreadState {
+    pub id: u8,
+    pub is_running: bool,
+    pub pc: u16,
+    pub registers: Vec<u32>,
+    pub zero_flag: bool,
+    pub carry_flag: bool
+}
+
+#[derive(Debug, Clone)]
+pub struct FaultReport {
+    pub dpu: DpuId,
+    pub cause: FaultCause,
+    pub thread: Option<u8>,
+    pub threads: Vec<ThreadState>,
+    pub atomic_register: Vec<bool>
+}
+
+const NOT_SCHEDULED: u8 = 0xFF;
+const NR_OF_REGISTERS_PER_LINE: usize = 8;
+
+impl FaultReport {
+    pub fn new(dpu: DpuId, context: &DpuDebugContext) -> Self {
+        // hardware faults take precedence over breakpoints when several are raised at once
+        let (cause, thread) = if let Some(thread) = context.dma_fault() {
+            (FaultCause::Dma, Some(thread))
+        } else if let Some(thread) = context.memory_fault() {
+            (FaultCause::Memory, Some(thread))
+        } else if let Some(thread) = context.breakpoint_fault() {
+            (FaultCause::Breakpoint, Some(thread))
+        } else {
+            (FaultCause::Unknown, None)
+        };
+
+        let nr_of_threads = context.pcs.len();
+        let nr_of_registers = context.registers.len().checked_div(nr_of_threads).unwrap_or(0);
+
+        let threads = (0..nr_of_threads)
+            .map(|id| ThreadState {
+                id: id as u8,
+                is_running: context.scheduling[id] != NOT_SCHEDULED,
+                pc: context.pcs[id],
+                registers: context.registers[(id * nr_of_registers)..((id + 1) * nr_of_registers)].to_vec(),
+                zero_flag: context.zero_flags[id],
+                carry_flag: context.carry_flags[id],
+            })
+            .collect();
+
+        FaultReport { dpu, cause, thread, threads, atomic_register: context.atomic_register.clone() }
+    }
+
+    pub fn faulting_thread(&self) -> Option<&ThreadState> {
+        self.thread.and_then(|thread| self.threads.get(thread as usize))
+    }
+}
+
+impl fmt::Display for FaultCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            FaultCause::Breakpoint => "breakpoint",
+            FaultCause::Memory => "memory fault",
+            FaultCause::Dma => "DMA fault",
+            FaultCause::Unknown => "unknown fault",
+        };
+
+        f.write_str(description)
+    }
+}
+
+impl fmt::Display for FaultReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.thread {
+            Some(thread) => writeln!(f, "{} on DPU {} (thread {})", self.cause, self.dpu, thread)?,
+            None => writeln!(f, "{} on DPU {}", self.cause, self.dpu)?,
+        }
+
+        for thread in self.threads.iter().filter(|thread| thread.is_running || Some(thread.id) == self.thread) {
+            writeln!(f, "  thread {:2}: pc = 0x{:04x} z = {} c = {}",
+                     thread.id, thread.pc, thread.zero_flag as u8, thread.carry_flag as u8)?;
+
+            for (line, registers) in thread.registers.chunks(NR_OF_REGISTERS_PER_LINE).enumerate() {
+                write!(f, "    r{:<2}", line * NR_OF_REGISTERS_PER_LINE)?;
+                for register in registers {
+                    write!(f, " {:08x}", register)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use crate::cluster::Cluster;
+use crate::dpu::DpuId;
+use crate::dpu::ProcessId;
+use crate::view::View;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LeasePolicy {
+    #[default]
+    Any,
+    PackBySlice,
+    PackByRank
+}
+
+#[derive(Debug)]
+pub struct DpuLease {
+    cluster: Arc<Cluster>,
+    owner: ProcessId,
+    dpus: Vec<DpuId>
+}
+
+impl DpuLease {
+    pub(crate) fn new(cluster: Arc<Cluster>, owner: ProcessId, dpus: Vec<DpuId>) -> Self {
+        DpuLease { cluster, owner, dpus }
+    }
+
+    pub fn cluster(&self) -> &Arc<Cluster> {
+        &self.cluster
+    }
+
+    pub fn owner(&self) -> ProcessId {
+        self.owner
+    }
+
+    pub fn dpus(&self) -> &[DpuId] {
+        &self.dpus
+    }
+
+    pub fn view(&self) -> View {
+        View::from_dpus(&self.dpus, self.cluster.topology())
+    }
+}
+
+impl Drop for DpuLease {
+    fn drop(&mut self) {
+        self.cluster.release(&self.owner, &self.dpus);
+    }
+}
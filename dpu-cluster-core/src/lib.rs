@@ -1,4 +1,9 @@
+// #[derive(DpuPod)] names the crate by its absolute path, which also has to resolve inside of it
+extern crate self as dpu_cluster_core;
+
+pub mod catalog;
 pub mod config;
+pub mod debugger;
 pub mod cluster;
 pub mod dpu;
 pub mod pipeline;
@@ -6,4 +11,9 @@ pub mod memory;
 pub mod program;
 pub mod driver;
 pub mod error;
+pub mod fault;
+pub mod lease;
+pub mod lock;
+pub mod pod;
 pub mod view;
+pub mod backend;
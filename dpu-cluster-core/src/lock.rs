@@ -0,0 +1,90 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use dpu_sys::DpuType;
+use crate::error::ClusterError;
+
+pub const DEFAULT_LOCK_DIRECTORY: &str = "/var/lock";
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug)]
+pub struct FileLock(File);
+
+// The CNI does not identify ranks: a process holds one locked rank slot per allocated rank, out of one
+// slot per rank of the machine. The type lock serializes the allocations between processes.
+pub fn lock_type(directory: &Path, dpu_type: DpuType, deadline: Option<Instant>) -> Result<FileLock, ClusterError> {
+    let path = directory.join(format!("dpu-{:?}.lock", dpu_type).to_lowercase());
+
+    loop {
+        if let Some(lock) = FileLock::try_acquire(&path)? {
+            return Ok(lock);
+        }
+
+        wait_until(deadline)?;
+    }
+}
+
+// to be called with the type lock held, before allocating the rank
+pub fn lock_rank_slot(directory: &Path, dpu_type: DpuType, nr_of_slots: usize) -> Result<FileLock, ClusterError> {
+    // one slot per rank of the machine: when all of them are locked, every rank is in use
+    for slot in 0..nr_of_slots {
+        let path = directory.join(format!("dpu-{:?}-rank-{}.lock", dpu_type, slot).to_lowercase());
+
+        if let Some(lock) = FileLock::try_acquire(&path)? {
+            return Ok(lock);
+        }
+    }
+
+    Err(ClusterError::RankBusy)
+}
+
+pub fn needs_lock(dpu_type: DpuType) -> bool {
+    // simulated ranks are private to the process
+    matches!(dpu_type, DpuType::Hardware | DpuType::BackupSpi)
+}
+
+pub fn wait_until(deadline: Option<Instant>) -> Result<(), ClusterError> {
+    match deadline {
+        Some(deadline) if Instant::now() < deadline => {
+            thread::sleep(RETRY_INTERVAL);
+            Ok(())
+        },
+        _ => Err(ClusterError::RankBusy),
+    }
+}
+
+impl FileLock {
+    fn try_acquire(path: &Path) -> Result<Option<FileLock>, ClusterError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|err| ClusterError::LockFailure(err.kind()))?;
+
+        let status = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+
+        if status == 0 {
+            return Ok(Some(FileLock(file)));
+        }
+
+        let err = io::Error::last_os_error();
+        match err.kind() {
+            io::ErrorKind::WouldBlock => Ok(None),
+            kind => Err(ClusterError::LockFailure(kind)),
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // closing the file would release the lock as well
+        unsafe { libc::flock(self.0.as_raw_fd(), libc::LOCK_UN); }
+    }
+}
@@ -1,76 +1,455 @@
 use crate::dpu::DpuId;
+use crate::error::ClusterError;
+use crate::pod;
+use crate::pod::DpuPod;
+use crate::program::Program;
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::mem;
 use std::collections::hash_map::Entry;
 
-pub struct MemoryTransferEntry<'a> {
+pub struct MemoryTransferEntry<B> {
     pub offset: u32,
-    pub reference: &'a mut [u8]
+    pub reference: B
 }
 
-pub struct MemoryTransferEntryReference<'a>(&'a mut [u8]);
+pub struct MemoryTransferEntryReference<B>(B);
 
-impl <'a> MemoryTransferEntry<'a> {
-    pub fn ptr(&mut self) -> *mut u8 {
-        self.reference.as_mut_ptr()
+// The CNI only writes into the buffers of DPU-to-host transfers: the pointers of host-to-DPU buffers
+// are never exposed outside of the crate.
+pub(crate) trait TransferBuffer: AsRef<[u8]> {
+    fn as_transfer_ptr(&mut self) -> *mut u8;
+}
+
+impl TransferBuffer for &[u8] {
+    fn as_transfer_ptr(&mut self) -> *mut u8 {
+        self.as_ptr() as *mut u8
     }
 }
 
-impl <'a> From<&'a mut [u8]> for MemoryTransferEntryReference<'a> {
-    fn from(v: &'a mut [u8]) -> Self {
-        MemoryTransferEntryReference(v)
+impl TransferBuffer for &mut [u8] {
+    fn as_transfer_ptr(&mut self) -> *mut u8 {
+        self.as_mut_ptr()
     }
 }
 
-impl <'a> From<&'a mut [u32]> for MemoryTransferEntryReference<'a> {
-    fn from(v: &'a mut [u32]) -> Self {
-        let u8_ref = unsafe {
-            std::slice::from_raw_parts_mut(
-                v.as_ptr() as *mut u8,
-                v.len() * std::mem::size_of::<u32>()
-            )
-        };
+impl <'a, T: DpuPod> From<&'a mut [T]> for MemoryTransferEntryReference<&'a mut [u8]> {
+    fn from(v: &'a mut [T]) -> Self {
+        MemoryTransferEntryReference(pod::as_bytes_mut(v))
+    }
+}
+
+impl <'a, T: DpuPod> From<&'a mut T> for MemoryTransferEntryReference<&'a mut [u8]> {
+    fn from(v: &'a mut T) -> Self {
+        MemoryTransferEntryReference(pod::as_bytes_mut(std::slice::from_mut(v)))
+    }
+}
 
-        MemoryTransferEntryReference(u8_ref)
+impl <'a, T: DpuPod> From<&'a [T]> for MemoryTransferEntryReference<&'a [u8]> {
+    fn from(v: &'a [T]) -> Self {
+        MemoryTransferEntryReference(pod::as_bytes(v))
     }
 }
 
-pub struct MemoryTransferRankEntry<'a>(pub HashMap<DpuId, MemoryTransferEntry<'a>>);
-pub struct MemoryTransfer<'a>(pub HashMap<u8, MemoryTransferRankEntry<'a>>);
+impl <'a, T: DpuPod> From<&'a mut [T]> for MemoryTransferEntryReference<&'a [u8]> {
+    fn from(v: &'a mut [T]) -> Self {
+        MemoryTransferEntryReference(pod::as_bytes(v))
+    }
+}
+
+impl <'a, T: DpuPod> From<&'a T> for MemoryTransferEntryReference<&'a [u8]> {
+    fn from(v: &'a T) -> Self {
+        MemoryTransferEntryReference(pod::as_bytes(std::slice::from_ref(v)))
+    }
+}
+
+// A DPU may have several regions, transferred in the order they were added
+pub struct MemoryTransferRankEntry<B>(pub HashMap<DpuId, Vec<MemoryTransferEntry<B>>>);
+pub struct MemoryTransfer<B> {
+    pub ranks: HashMap<u8, MemoryTransferRankEntry<B>>,
+    pub unaligned_regions: UnalignedRegions
+}
+
+pub const MRAM_ALIGNMENT: u32 = 8;
 
-impl <'a> Default for MemoryTransferRankEntry<'a> {
+// The MRAM is only accessible by aligned blocks of 8 bytes. Bounced regions are transferred through
+// aligned buffers: the bytes around a region written to a DPU are read first, and kept as they were.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum UnalignedRegions {
+    #[default]
+    Reject,
+    Bounce
+}
+
+pub type ToDpuTransfer<'a> = MemoryTransfer<&'a [u8]>;
+pub type FromDpuTransfer<'a> = MemoryTransfer<&'a mut [u8]>;
+
+impl <B> Default for MemoryTransferRankEntry<B> {
     fn default() -> Self {
         MemoryTransferRankEntry(HashMap::default())
     }
 }
 
-impl <'a> Default for MemoryTransfer<'a> {
+impl <B> Default for MemoryTransfer<B> {
     fn default() -> Self {
-        MemoryTransfer(HashMap::default())
+        MemoryTransfer { ranks: HashMap::default(), unaligned_regions: UnalignedRegions::default() }
+    }
+}
+
+impl <B> MemoryTransfer<B> {
+    pub fn entries(&self) -> impl Iterator<Item=(&DpuId, usize, &MemoryTransferEntry<B>)> {
+        self.ranks.values()
+            .flat_map(|rank_transfers| rank_transfers.0.iter())
+            .flat_map(|(dpu, entries)| entries.iter().enumerate().map(move |(region, entry)| (dpu, region, entry)))
+    }
+}
+
+impl <B> MemoryTransferRankEntry<B> {
+    pub fn nr_of_regions(&self) -> usize {
+        self.0.values().map(Vec::len).max().unwrap_or(0)
     }
 }
 
-impl <'a> MemoryTransfer<'a> {
+impl <B: AsRef<[u8]>> MemoryTransfer<B> {
+    pub fn bouncing_unaligned_regions(mut self) -> Self {
+        self.unaligned_regions = UnalignedRegions::Bounce;
+        self
+    }
+
+    pub(crate) fn check_bounds(&self, limit: u32) -> Result<(), ClusterError> {
+        for (dpu, _, entry) in self.entries() {
+            check_mram_bounds(*dpu, entry.offset, entry.reference.as_ref().len(), limit)?;
+        }
+
+        Ok(())
+    }
+
+    // every region is checked before any of them is transferred
+    pub(crate) fn has_unaligned_regions(&self) -> Result<bool, ClusterError> {
+        let mut found = false;
+
+        for rank_transfers in self.ranks.values() {
+            for (dpu, entries) in &rank_transfers.0 {
+                for entry in entries {
+                    let length = entry.reference.as_ref().len();
+
+                    if !is_mram_aligned(entry.offset, length) {
+                        if self.unaligned_regions == UnalignedRegions::Reject {
+                            return Err(ClusterError::UnalignedMramAccess { dpu: *dpu, offset: entry.offset, length: length as u32 });
+                        }
+                        found = true;
+                    }
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
     pub fn add<I>(mut self, dpu: DpuId, offset: u32, slice: I) -> Self
-        where I: Into<MemoryTransferEntryReference<'a>>
+        where I: Into<MemoryTransferEntryReference<B>>
     {
         self.add_entry(dpu, MemoryTransferEntry {offset, reference: slice.into().0});
         self
     }
 
+    pub fn add_at_symbol<I>(mut self, program: &Program, dpu: DpuId, symbol: &str, offset: u32, slice: I) -> Result<Self, ClusterError>
+        where I: Into<MemoryTransferEntryReference<B>>
+    {
+        let reference = slice.into().0;
+        let offset = program.resolve_in_mram(symbol, offset, reference.as_ref().len() as u32)?;
+        self.add_entry(dpu, MemoryTransferEntry {offset, reference});
+        Ok(self)
+    }
+
     pub fn add_in_place<I>(&mut self, dpu: DpuId, offset: u32, slice: I)
-        where I: Into<MemoryTransferEntryReference<'a>>
+        where I: Into<MemoryTransferEntryReference<B>>
     {
         self.add_entry(dpu, MemoryTransferEntry {offset, reference: slice.into().0});
     }
 
-    fn add_entry(&mut self, dpu: DpuId, entry: MemoryTransferEntry<'a>) {
+    pub fn add_in_region<T, I>(mut self, dpu: DpuId, allocator: &MramAllocator, region: &MramRegion<T>, slice: I) -> Result<Self, ClusterError>
+        where I: Into<MemoryTransferEntryReference<B>>
+    {
+        let reference = slice.into().0;
+        allocator.check(&dpu, region)?;
+        region.check_length(reference.as_ref().len())?;
+        self.add_entry(dpu, MemoryTransferEntry {offset: region.offset, reference});
+        Ok(self)
+    }
+
+    fn add_entry(&mut self, dpu: DpuId, entry: MemoryTransferEntry<B>) {
         let (rank_id, _, _) = dpu.members();
 
-        let rank_transfers = match self.0.entry(rank_id) {
+        let rank_transfers = match self.ranks.entry(rank_id) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => entry.insert(MemoryTransferRankEntry::default()),
         };
 
-        rank_transfers.0.insert(dpu, entry);
+        rank_transfers.0.entry(dpu).or_default().push(entry);
+    }
+}
+
+pub fn is_mram_aligned(offset: u32, length: usize) -> bool {
+    offset.is_multiple_of(MRAM_ALIGNMENT) && length.is_multiple_of(MRAM_ALIGNMENT as usize)
+}
+
+pub(crate) fn check_mram_bounds(dpu: DpuId, offset: u32, length: usize, limit: u32) -> Result<(), ClusterError> {
+    if offset as u64 + length as u64 > limit as u64 {
+        Err(ClusterError::OutOfBounds { dpu, offset, length: length as u32, limit })
+    } else {
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+pub(crate) fn mram_aligned_range(offset: u32, length: usize) -> (u32, u32) {
+    let start = offset - offset % MRAM_ALIGNMENT;
+    let end = (offset + length as u32).div_ceil(MRAM_ALIGNMENT) * MRAM_ALIGNMENT;
+
+    (start, end)
+}
+
+// A named region of the MRAM, holding `len()` values of T. The handle is only valid on the DPUs it was
+// allocated on, until the region is freed or resized.
+#[derive(Debug)]
+pub struct MramRegion<T> {
+    name: String,
+    offset: u32,
+    length: u32,
+    id: u64,
+    dpus: Vec<DpuId>,
+    content: PhantomData<T>
+}
+
+impl <T> Clone for MramRegion<T> {
+    fn clone(&self) -> Self {
+        MramRegion { name: self.name.clone(), offset: self.offset, length: self.length, id: self.id, dpus: self.dpus.clone(), content: PhantomData }
+    }
+}
+
+impl <T> MramRegion<T> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn byte_length(&self) -> u32 {
+        self.length
+    }
+
+    pub fn len(&self) -> u32 {
+        self.length / (mem::size_of::<T>().max(1) as u32)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dpus(&self) -> &[DpuId] {
+        &self.dpus
+    }
+
+    pub(crate) fn as_bytes(&self) -> MramRegion<u8> {
+        MramRegion { name: self.name.clone(), offset: self.offset, length: self.length, id: self.id, dpus: self.dpus.clone(), content: PhantomData }
+    }
+
+    pub(crate) fn check_length(&self, length: usize) -> Result<(), ClusterError> {
+        if length as u64 > self.length as u64 {
+            Err(ClusterError::RegionOverflow { region: self.name.clone(), length: length as u32, size: self.length })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MramUsage {
+    pub used: u32,
+    pub free: u32,
+    pub largest_free_block: u32,
+    pub nr_of_regions: usize
+}
+
+impl MramUsage {
+    // 0 when all the free space is contiguous, close to 1 when it is scattered in small blocks
+    pub fn fragmentation(&self) -> f64 {
+        if self.free == 0 {
+            0.0
+        } else {
+            1.0 - (self.largest_free_block as f64) / (self.free as f64)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Allocation {
+    name: String,
+    offset: u32,
+    length: u32,
+    // changes when the region is resized, for the previous handles to be rejected
+    id: u64
+}
+
+// Regions are placed first-fit, and aligned on MRAM blocks so that they can be transferred as they are.
+// Allocating a region on several DPUs at once places it at the same offset on all of them, which lets
+// the pipeline transfers, which do not know their DPU in advance, use it.
+#[derive(Debug, Default)]
+pub struct MramAllocator {
+    mram_size: u32,
+    dpus: HashMap<DpuId, Vec<Allocation>>,
+    last_id: u64
+}
+
+impl MramAllocator {
+    pub fn new(mram_size: u32) -> Self {
+        MramAllocator { mram_size: mram_size - mram_size % MRAM_ALIGNMENT, dpus: HashMap::default(), last_id: 0 }
+    }
+
+    pub fn allocate<T>(&mut self, dpu: DpuId, name: &str, len: u32) -> Result<MramRegion<T>, ClusterError> {
+        self.allocate_on(&[dpu], name, len)
+    }
+
+    pub fn allocate_on<T>(&mut self, dpus: &[DpuId], name: &str, len: u32) -> Result<MramRegion<T>, ClusterError> {
+        for dpu in dpus {
+            if self.find(dpu, name).is_some() {
+                return Err(ClusterError::RegionAlreadyAllocated { dpu: *dpu, region: name.to_string() });
+            }
+        }
+
+        let length = (len as u64) * (mem::size_of::<T>() as u64);
+        let offset = self.first_fit(dpus, length, None).ok_or_else(|| self.not_enough_mram(dpus, length))?;
+        let id = self.next_id();
+
+        for dpu in dpus {
+            let allocations = self.dpus.entry(*dpu).or_default();
+            allocations.push(Allocation { name: name.to_string(), offset, length: length as u32, id });
+            allocations.sort_by_key(|allocation| allocation.offset);
+        }
+
+        Ok(MramRegion { name: name.to_string(), offset, length: length as u32, id, dpus: dpus.to_vec(), content: PhantomData })
+    }
+
+    pub fn region<T>(&self, dpu: &DpuId, name: &str) -> Option<MramRegion<T>> {
+        self.find(dpu, name).map(|allocation| MramRegion {
+            name: allocation.name.clone(), offset: allocation.offset, length: allocation.length, id: allocation.id, dpus: vec![*dpu], content: PhantomData
+        })
+    }
+
+    // rejects the handles of freed or resized regions, and of regions allocated on other DPUs
+    pub fn check<T>(&self, dpu: &DpuId, region: &MramRegion<T>) -> Result<(), ClusterError> {
+        match self.find(dpu, &region.name) {
+            Some(allocation) if allocation.id == region.id && region.dpus.contains(dpu) => Ok(()),
+            _ => Err(ClusterError::UnknownRegion { dpu: *dpu, region: region.name.clone() }),
+        }
+    }
+
+    pub fn free(&mut self, dpu: &DpuId, name: &str) -> Result<(), ClusterError> {
+        let allocations = self.dpus.get_mut(dpu);
+        let index = allocations.as_ref().and_then(|allocations| allocations.iter().position(|allocation| allocation.name == name));
+
+        match (allocations, index) {
+            (Some(allocations), Some(index)) => {
+                allocations.remove(index);
+                Ok(())
+            },
+            _ => Err(ClusterError::UnknownRegion { dpu: *dpu, region: name.to_string() }),
+        }
+    }
+
+    // A region which cannot grow in place is moved: its content is not copied.
+    pub fn resize<T>(&mut self, dpu: &DpuId, name: &str, len: u32) -> Result<MramRegion<T>, ClusterError> {
+        let current = self.find(dpu, name).cloned().ok_or_else(|| ClusterError::UnknownRegion { dpu: *dpu, region: name.to_string() })?;
+        let length = (len as u64) * (mem::size_of::<T>() as u64);
+
+        let offset = if self.fits_at(dpu, current.offset, length, Some(name)) {
+            current.offset
+        } else {
+            self.first_fit(&[*dpu], length, Some(name)).ok_or_else(|| self.not_enough_mram(&[*dpu], length))?
+        };
+
+        let id = self.next_id();
+        // unwrap: the region was found above
+        let allocations = self.dpus.get_mut(dpu).unwrap();
+        if let Some(allocation) = allocations.iter_mut().find(|allocation| allocation.name == name) {
+            allocation.offset = offset;
+            allocation.length = length as u32;
+            allocation.id = id;
+        }
+        allocations.sort_by_key(|allocation| allocation.offset);
+
+        Ok(MramRegion { name: name.to_string(), offset, length: length as u32, id, dpus: vec![*dpu], content: PhantomData })
+    }
+
+    pub fn usage(&self, dpu: &DpuId) -> MramUsage {
+        let free_blocks = self.free_blocks(&[*dpu], None);
+        let free = free_blocks.iter().map(|(start, end)| end - start).sum::<u64>() as u32;
+        let largest_free_block = free_blocks.iter().map(|(start, end)| end - start).max().unwrap_or(0) as u32;
+        let nr_of_regions = self.dpus.get(dpu).map(Vec::len).unwrap_or(0);
+
+        MramUsage { used: self.mram_size - free, free, largest_free_block, nr_of_regions }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn find(&self, dpu: &DpuId, name: &str) -> Option<&Allocation> {
+        self.dpus.get(dpu).and_then(|allocations| allocations.iter().find(|allocation| allocation.name == name))
+    }
+
+    // the blocks free on all the given DPUs, as aligned (start, end) ranges
+    fn free_blocks(&self, dpus: &[DpuId], ignored: Option<&str>) -> Vec<(u64, u64)> {
+        let mut used = dpus.iter()
+            .filter_map(|dpu| self.dpus.get(dpu))
+            .flat_map(|allocations| allocations.iter())
+            .filter(|allocation| Some(allocation.name.as_str()) != ignored)
+            .map(|allocation| {
+                let (start, end) = mram_aligned_range(allocation.offset, allocation.length as usize);
+                (start as u64, end as u64)
+            })
+            .collect::<Vec<_>>();
+        used.sort();
+
+        let mut blocks = Vec::default();
+        let mut position = 0;
+        for (start, end) in used {
+            if start > position {
+                blocks.push((position, start));
+            }
+            position = position.max(end);
+        }
+        if (self.mram_size as u64) > position {
+            blocks.push((position, self.mram_size as u64));
+        }
+
+        blocks
+    }
+
+    fn first_fit(&self, dpus: &[DpuId], length: u64, ignored: Option<&str>) -> Option<u32> {
+        let length = length.div_ceil(MRAM_ALIGNMENT as u64) * (MRAM_ALIGNMENT as u64);
+
+        self.free_blocks(dpus, ignored).into_iter()
+            .find(|(start, end)| end - start >= length)
+            .map(|(start, _)| start as u32)
+    }
+
+    fn fits_at(&self, dpu: &DpuId, offset: u32, length: u64, ignored: Option<&str>) -> bool {
+        let end = offset as u64 + length;
+
+        self.free_blocks(&[*dpu], ignored).into_iter()
+            .any(|(start, block_end)| start <= offset as u64 && end <= block_end)
+    }
+
+    fn not_enough_mram(&self, dpus: &[DpuId], length: u64) -> ClusterError {
+        let (dpu, largest_free_block) = dpus.iter()
+            .map(|dpu| (*dpu, self.usage(dpu).largest_free_block))
+            .min_by_key(|(_, largest_free_block)| *largest_free_block)
+            .unwrap_or((DpuId::new(0, 0, 0), 0));
+
+        ClusterError::NotEnoughMram { dpu, requested: length.min(u32::MAX as u64) as u32, largest_free_block }
+    }
+}
@@ -9,6 +9,7 @@ pub mod output;
 pub mod monitoring;
 
 mod stages;
+#[allow(clippy::module_inception)]
 mod pipeline;
 
 #[derive(Debug)]
@@ -27,17 +28,13 @@ impl From<ClusterError> for PipelineError {
     }
 }
 
+#[derive(Default)]
 pub enum GroupPolicy {
     Dpu,
+    #[default]
     Slice
 }
 
-impl Default for GroupPolicy {
-    fn default() -> Self {
-        GroupPolicy::Slice
-    }
-}
-
 type OutputResult<K> = Result<(K, Vec<u8>), PipelineError>;
 type ThreadHandle = Option<JoinHandle<()>>;
 
@@ -21,8 +21,9 @@ pub enum Event {
     OutputFetchingEnd(GroupId),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Process {
+    #[default]
     Pipeline,
     Initializer,
     Mapper,
@@ -31,24 +32,13 @@ pub enum Process {
     Fetcher
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum RecordPolicy {
+    #[default]
     Disabled,
     Stdout
 }
 
-impl Default for Process {
-    fn default() -> Self {
-        Process::Pipeline
-    }
-}
-
-impl Default for RecordPolicy {
-    fn default() -> Self {
-        RecordPolicy::Disabled
-    }
-}
-
 #[derive(Clone)]
 pub struct EventMonitor {
     process: Process,
@@ -72,7 +62,7 @@ impl EventMonitor {
         match self.policy {
             RecordPolicy::Disabled => {},
             RecordPolicy::Stdout => {
-                println!("[{}][{:?}] {:?}", Local::now().format("%F %T%.f").to_string(), self.process, event);
+                println!("[{}][{:?}] {:?}", Local::now().format("%F %T%.f"), self.process, event);
             },
         }
     }
@@ -23,6 +23,8 @@ use crate::pipeline::stages::mapper::PersistentMapper;
 use std::hash::Hash;
 use crate::pipeline::transfer::InputMemoryTransfer;
 use crate::pipeline::PipelineError;
+use crate::lease::DpuLease;
+use std::collections::BTreeMap;
 
 pub struct Pipeline<K> {
     pub output_receiver: Receiver<OutputResult<K>>,
@@ -32,12 +34,14 @@ pub struct Pipeline<K> {
     input_loader: ThreadHandle,
     execution_tracker: ThreadHandle,
     output_fetcher: ThreadHandle,
-    shutdown: Arc<Mutex<bool>>
+    shutdown: Arc<Mutex<bool>>,
+    // released once the stages are joined
+    _lease: Option<DpuLease>
 }
 
 impl <K: Send + 'static> Pipeline<K> {
     // todo factorize simple and persistent?
-    pub fn simple<I, F, IT>(iterator: Box<IT>, cluster: Arc<Cluster>, transfers_fn: Box<F>,
+    pub fn simple<I, F, IT>(iterator: Box<IT>, cluster: Arc<Cluster>, lease: Option<DpuLease>, transfers_fn: Box<F>,
                             group_policy: GroupPolicy, monitoring: EventMonitor) -> Result<Self, PipelineError>
         where I: Send + 'static,
               IT: Iterator<Item=I> + Send + 'static,
@@ -56,7 +60,11 @@ impl <K: Send + 'static> Pipeline<K> {
         let (incoming_job_tx, incoming_job_rx) = channel();
         let (finished_job_tx, finished_job_rx) = channel();
 
-        let groups = create_groups_from(group_policy, nr_ranks, nr_slices, nr_dpus);
+        let dpus = match lease {
+            Some(ref lease) => lease.dpus().to_vec(),
+            None => all_dpus(nr_ranks, nr_slices, nr_dpus),
+        };
+        let groups = create_groups_from(group_policy, &dpus, nr_slices, nr_dpus);
 
         // todo: tracker_sleep_duration should be a config parameter
         let tracker_sleep_duration = match cluster.target().dpu_type {
@@ -71,7 +79,8 @@ impl <K: Send + 'static> Pipeline<K> {
 
         let input_mapper = SimpleMapper::new(
             transfers_fn, groups, input_rx, group_rx,
-            transfer_tx, monitoring.clone(), shutdown.clone()
+            cluster.clone(), transfer_tx, output_tx.clone(),
+            monitoring.clone(), shutdown.clone()
         ).launch()?;
 
         let input_loader = InputLoader::new(
@@ -97,12 +106,14 @@ impl <K: Send + 'static> Pipeline<K> {
             input_loader,
             execution_tracker,
             output_fetcher,
-            shutdown
+            shutdown,
+            _lease: lease
         })
     }
 
-    pub fn persistent<I, F, IT, D, DIT>(iterator: Box<IT>, cluster: Arc<Cluster>, transfers_fn: Box<F>,
-                                           mapping_iterator: Box<DIT>,
+    #[allow(clippy::too_many_arguments)]
+    pub fn persistent<I, F, IT, D, DIT>(iterator: Box<IT>, cluster: Arc<Cluster>, lease: Option<DpuLease>, transfers_fn: Box<F>,
+                                           mapping_iterator: Box<DIT>, dataset: Option<String>,
                                            group_policy: GroupPolicy, monitoring: EventMonitor) -> Result<Self, PipelineError>
         where I: Send + 'static,
               IT: Iterator<Item=I> + Send + 'static,
@@ -123,7 +134,11 @@ impl <K: Send + 'static> Pipeline<K> {
         let (incoming_job_tx, incoming_job_rx) = channel();
         let (finished_job_tx, finished_job_rx) = channel();
 
-        let groups = create_groups_from(group_policy, nr_ranks, nr_slices, nr_dpus);
+        let dpus = match lease {
+            Some(ref lease) => lease.dpus().to_vec(),
+            None => all_dpus(nr_ranks, nr_slices, nr_dpus),
+        };
+        let groups = create_groups_from(group_policy, &dpus, nr_slices, nr_dpus);
 
         // todo: tracker_sleep_duration should be a config parameter
         let tracker_sleep_duration = match cluster.target().dpu_type {
@@ -139,7 +154,7 @@ impl <K: Send + 'static> Pipeline<K> {
         let input_mapper = PersistentMapper::new(
             transfers_fn, groups, input_rx, group_rx,
             cluster.clone(), transfer_tx, output_tx.clone(),
-            mapping_iterator, monitoring.clone(), shutdown.clone()
+            mapping_iterator, dataset, monitoring.clone(), shutdown.clone()
         ).launch()?;
 
         let input_loader = InputLoader::new(
@@ -165,44 +180,52 @@ impl <K: Send + 'static> Pipeline<K> {
             input_loader,
             execution_tracker,
             output_fetcher,
-            shutdown
+            shutdown,
+            _lease: lease
         })
     }
 }
 
-fn create_groups_from(policy: GroupPolicy, nr_ranks: u8, nr_slices: u8, nr_dpus: u8) -> Vec<DpuGroup> {
-    match policy {
-        GroupPolicy::Dpu => {
-            let mut vec = Vec::with_capacity((nr_ranks as usize) * (nr_slices as usize) * (nr_dpus as usize));
-
-            for rank_idx in 0..nr_ranks {
-                for slice_idx in 0..nr_slices {
-                    for dpu_idx in 0..nr_dpus {
-                        let id = ((rank_idx as u32) * (nr_slices as u32) *  (nr_dpus as u32)) + ((slice_idx as u32) * (nr_dpus as u32)) + (dpu_idx as u32);
-                        let dpus = vec![(DpuId::new(rank_idx, slice_idx, dpu_idx), true)];
-                        vec.push(DpuGroup { id, dpus });
-                    }
-                }
+fn all_dpus(nr_ranks: u8, nr_slices: u8, nr_dpus: u8) -> Vec<DpuId> {
+    let mut vec = Vec::with_capacity((nr_ranks as usize) * (nr_slices as usize) * (nr_dpus as usize));
+
+    for rank_idx in 0..nr_ranks {
+        for slice_idx in 0..nr_slices {
+            for dpu_idx in 0..nr_dpus {
+                vec.push(DpuId::new(rank_idx, slice_idx, dpu_idx));
             }
+        }
+    }
+
+    vec
+}
 
-            vec
+fn create_groups_from(policy: GroupPolicy, dpus: &[DpuId], nr_slices: u8, nr_dpus: u8) -> Vec<DpuGroup> {
+    match policy {
+        GroupPolicy::Dpu => {
+            dpus.iter()
+                .map(|dpu| {
+                    let (rank_idx, slice_idx, dpu_idx) = dpu.members();
+                    let id = ((rank_idx as u32) * (nr_slices as u32) *  (nr_dpus as u32)) + ((slice_idx as u32) * (nr_dpus as u32)) + (dpu_idx as u32);
+                    DpuGroup { id, dpus: vec![(*dpu, true)] }
+                })
+                .collect()
         },
         GroupPolicy::Slice => {
-            let mut vec = Vec::with_capacity((nr_ranks as usize) * (nr_dpus as usize));
-
-            for rank_idx in 0..nr_ranks {
-                for dpu_idx in 0..nr_dpus {
-                    let mut dpus = Vec::with_capacity(nr_slices as usize);
-
-                    for slice_idx in 0..nr_slices {
-                        dpus.push((DpuId::new(rank_idx, slice_idx, dpu_idx), true));
-                    }
+            let mut groups = BTreeMap::<_, Vec<_>>::default();
 
-                    vec.push(DpuGroup { id: ((rank_idx as u32) * (nr_dpus as u32)) + (dpu_idx as u32), dpus } );
-                }
+            for dpu in dpus {
+                let (rank_idx, slice_idx, dpu_idx) = dpu.members();
+                groups.entry((rank_idx, dpu_idx)).or_default().push((slice_idx, *dpu));
             }
 
-            vec
+            groups.into_iter()
+                .map(|((rank_idx, dpu_idx), mut members)| {
+                    members.sort_by_key(|(slice_idx, _)| *slice_idx);
+                    let dpus = members.into_iter().map(|(_, dpu)| (dpu, true)).collect();
+                    DpuGroup { id: ((rank_idx as u32) * (nr_dpus as u32)) + (dpu_idx as u32), dpus }
+                })
+                .collect()
         }
     }
 }
@@ -12,10 +12,12 @@ use std::sync::Arc;
 use crate::pipeline::monitoring::RecordPolicy;
 use crate::pipeline::transfer::InputMemoryTransfer;
 use std::hash::Hash;
+use crate::lease::DpuLease;
 
 pub struct Plan<'a, Model, InputIterator> {
     input_iterator: Box<InputIterator>,
-    cluster: Option<Cluster>,
+    cluster: Option<Arc<Cluster>>,
+    lease: Option<DpuLease>,
     program: Option<&'a Program>,
     group_policy: GroupPolicy,
     monitoring: EventMonitor,
@@ -31,6 +33,7 @@ impl <'a, InputItem, InputIterator> Plan<'a, (), InputIterator>
         Plan {
             input_iterator: Box::new(iterator.into_iter()),
             cluster: None,
+            lease: None,
             program: None,
             group_policy: GroupPolicy::default(),
             monitoring: EventMonitor::default(),
@@ -46,7 +49,14 @@ impl <'a, Model, InputIterator> Plan<'a, Model, InputIterator> {
     }
 
     pub fn driving(mut self, cluster: Cluster) -> Self {
-        self.cluster = Some(cluster);
+        self.cluster = Some(Arc::new(cluster));
+        self.lease = None;
+        self
+    }
+
+    pub fn leasing(mut self, lease: DpuLease) -> Self {
+        self.cluster = Some(lease.cluster().clone());
+        self.lease = Some(lease);
         self
     }
 
@@ -60,19 +70,22 @@ impl <'a, Model, InputIterator> Plan<'a, Model, InputIterator> {
         self
     }
 
-    fn build_init(cluster: &Cluster, monitoring: &EventMonitor, program: Option<&Program>) -> Result<(), PipelineError> {
+    fn build_init(cluster: &Cluster, lease: Option<&DpuLease>, monitoring: &EventMonitor, program: Option<&Program>) -> Result<(), PipelineError> {
         let (nr_ranks, nr_slices, nr_dpus) = cluster.topology();
 
         monitoring.record(Event::Initialization { nr_ranks, nr_slices, nr_dpus });
 
         if let Some(program) = program {
-            let nr_instructions = program.iram_sections.iter()
+            program.validate(&cluster.driver().rank_description)?;
+
+            let nr_instructions = program.iram_sections().iter()
                 .fold(0u32, |acc, (_, instructions)| acc + (instructions.len() as u32));
-            let nr_data_bytes = program.wram_sections.iter()
+            let nr_data_bytes = program.wram_sections().iter()
                 .fold(0u32, |acc, (_, data)| acc + ((data.len() as u32) * 4));
 
             monitoring.record(Event::LoadingProgramBegin { nr_instructions, nr_data_bytes });
-            cluster.driver().load(&View::all(), program)?;
+            let view = lease.map(DpuLease::view).unwrap_or_else(View::all);
+            cluster.driver().load(&view, program)?;
             monitoring.record(Event::LoadingProgramEnd);
         }
 
@@ -88,6 +101,7 @@ impl <'a, InputIterator> Plan<'a, (), InputIterator> {
         Plan {
             input_iterator: self.input_iterator,
             cluster: self.cluster,
+            lease: self.lease,
             program: self.program,
             group_policy: self.group_policy,
             monitoring: self.monitoring,
@@ -107,11 +121,11 @@ impl <'a, InputItem, InputIterator, InputHandle, TransferFn> Plan<'a, SimpleMode
           TransferFn: Fn(InputItem) -> MemoryTransfers<InputHandle> + Send + 'static
 {
     pub fn build(self) -> Result<Output<InputHandle>, PipelineError>  {
-        let cluster = self.cluster.ok_or_else(|| PipelineError::UndefinedCluster)?;
+        let cluster = self.cluster.ok_or(PipelineError::UndefinedCluster)?;
 
-        Self::build_init(&cluster, &self.monitoring, self.program)?;
+        Self::build_init(&cluster, self.lease.as_ref(), &self.monitoring, self.program)?;
 
-        let pipeline = Pipeline::simple(self.input_iterator, Arc::new(cluster),
+        let pipeline = Pipeline::simple(self.input_iterator, cluster, self.lease,
                                         self.model.input_transfers_fn, self.group_policy, self.monitoring)?;
 
         Ok(Output::new(pipeline))
@@ -121,6 +135,7 @@ impl <'a, InputItem, InputIterator, InputHandle, TransferFn> Plan<'a, SimpleMode
 pub struct PersistentModel<TransferFn, PersistentIterator> {
     persistent_iterator: Box<PersistentIterator>,
     input_transfers_fn: Box<TransferFn>,
+    dataset: Option<String>
 }
 
 impl <'a, InputIterator> Plan<'a, (), InputIterator> {
@@ -134,10 +149,11 @@ impl <'a, InputIterator> Plan<'a, (), InputIterator> {
         Plan {
             input_iterator: self.input_iterator,
             cluster: self.cluster,
+            lease: self.lease,
             program: self.program,
             group_policy: self.group_policy,
             monitoring: self.monitoring,
-            model: PersistentModel { input_transfers_fn: Box::new(func), persistent_iterator: Box::new(iterator.into_iter()) }
+            model: PersistentModel { input_transfers_fn: Box::new(func), persistent_iterator: Box::new(iterator.into_iter()), dataset: None }
         }
     }
 }
@@ -150,13 +166,21 @@ impl <'a, InputItem, InputIterator, InputHandle, TransferFn, PersistentHandle, P
           PersistentHandle: Eq + Hash + Send + 'static,
           PersistentIterator: Iterator<Item=(PersistentHandle, InputMemoryTransfer)> + Send + 'static
 {
+    // The fragments are recorded in the catalog of the cluster under this name, and stay available to the next
+    // plans using it, which may then only load new fragments.
+    pub fn cataloged_as(mut self, dataset: &str) -> Self {
+        self.model.dataset = Some(dataset.to_string());
+        self
+    }
+
     pub fn build(self) -> Result<Output<InputHandle>, PipelineError>  {
-        let cluster = self.cluster.ok_or_else(|| PipelineError::UndefinedCluster)?;
+        let cluster = self.cluster.ok_or(PipelineError::UndefinedCluster)?;
 
-        Self::build_init(&cluster, &self.monitoring, self.program)?;
+        Self::build_init(&cluster, self.lease.as_ref(), &self.monitoring, self.program)?;
 
-        let pipeline = Pipeline::persistent(self.input_iterator, Arc::new(cluster),
-                                        self.model.input_transfers_fn, self.model.persistent_iterator, self.group_policy, self.monitoring)?;
+        let pipeline = Pipeline::persistent(self.input_iterator, cluster, self.lease,
+                                        self.model.input_transfers_fn, self.model.persistent_iterator, self.model.dataset,
+                                        self.group_policy, self.monitoring)?;
 
         Ok(Output::new(pipeline))
     }
@@ -4,7 +4,7 @@ use std::sync::mpsc::Sender;
 use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 use std::sync::Mutex;
-use crate::memory::MemoryTransfer;
+use crate::memory::FromDpuTransfer;
 use crate::pipeline::PipelineError;
 use crate::pipeline::stages::GroupJob;
 use crate::cluster::Cluster;
@@ -21,6 +21,7 @@ pub struct OutputFetcher<InputHandle> {
     group_sender: Sender<DpuGroup>,
     monitoring: EventMonitor,
     // todo: use or remove
+    #[allow(dead_code)]
     shutdown: Arc<Mutex<bool>>
 }
 
@@ -58,10 +59,10 @@ impl <InputHandle> Stage for OutputFetcher<InputHandle>
             }
 
             let copy_result = {
-                let mut memory_transfer = MemoryTransfer::default();
+                let mut memory_transfer = FromDpuTransfer::default().bouncing_unaligned_regions();
                 for ((vector, offset, _), dpu) in vectors.iter_mut().zip(group.active_dpus()) {
-                    monitoring.record(Event::OutputFetchingInfo { dpu: dpu.clone(), offset: *offset, length: vector.len() as u32});
-                    memory_transfer.add_in_place(dpu.clone(), *offset, vector.as_mut_slice());
+                    monitoring.record(Event::OutputFetchingInfo { dpu: *dpu, offset: *offset, length: vector.len() as u32});
+                    memory_transfer.add_in_place(*dpu, *offset, vector.as_mut_slice());
                 }
                 self.cluster.driver().copy_from_memory(&mut memory_transfer)
             };
@@ -70,7 +71,8 @@ impl <InputHandle> Stage for OutputFetcher<InputHandle>
 
             match copy_result {
                 Ok(_) => {
-                    self.group_sender.send(group);
+                    // the mapper stops listening once all the inputs have been dispatched
+                    let _ = self.group_sender.send(group);
                     for (result, _, handle) in vectors {
                         self.output_sender.send(Ok((handle, result))).unwrap();
                     }
@@ -1,16 +1,18 @@
+use std::ptr;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
-use crate::pipeline::transfer::OutputMemoryTransfer;
 use crate::pipeline::PipelineError;
 use std::sync::Arc;
 use std::sync::Mutex;
 use crate::pipeline::transfer::InputMemoryTransfer;
 use crate::view::View;
+use crate::dpu::DpuId;
 use crate::pipeline::stages::DpuGroup;
 use crate::pipeline::OutputResult;
 use crate::pipeline::stages::GroupJob;
 use crate::cluster::Cluster;
-use crate::memory::MemoryTransfer;
+use crate::program::Program;
+use crate::memory::ToDpuTransfer;
 use crate::error::ClusterError;
 use crate::pipeline::monitoring::EventMonitor;
 use crate::pipeline::monitoring::Process;
@@ -18,14 +20,17 @@ use crate::pipeline::monitoring::Event;
 use crate::driver::Driver;
 use std::sync::mpsc::SyncSender;
 use crate::pipeline::stages::Stage;
+use crate::pipeline::stages::GroupTransfers;
+use crate::pipeline::stages::DpuInputs;
 
 pub struct InputLoader<InputHandle> {
     cluster: Arc<Cluster>,
-    transfer_receiver: Receiver<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(InputHandle, OutputMemoryTransfer)>)>,
+    transfer_receiver: Receiver<GroupTransfers<InputHandle>>,
     job_sender: Sender<GroupJob<InputHandle>>,
     output_sender: SyncSender<OutputResult<InputHandle>>,
     monitoring: EventMonitor,
     // todo: use or remove
+    #[allow(dead_code)]
     shutdown: Arc<Mutex<bool>>
 }
 
@@ -33,7 +38,7 @@ impl <InputHandle> InputLoader<InputHandle>
     where InputHandle: Send + 'static
 {
     pub fn new(cluster: Arc<Cluster>,
-               transfer_receiver: Receiver<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(InputHandle, OutputMemoryTransfer)>)>,
+               transfer_receiver: Receiver<GroupTransfers<InputHandle>>,
                job_sender: Sender<GroupJob<InputHandle>>,
                output_sender: SyncSender<OutputResult<InputHandle>>,
                mut monitoring: EventMonitor,
@@ -52,14 +57,12 @@ impl <InputHandle> Stage for InputLoader<InputHandle>
 
         monitoring.record(Event::ProcessBegin);
 
-        let driver = self.cluster.driver();
-
         for (group, inputs, outputs) in self.transfer_receiver {
             let group_id = group.id;
 
             monitoring.record(Event::GroupLoadingBegin(group_id));
 
-            let is_ok = load_input_chunk(driver, &group, inputs, &self.output_sender);
+            let is_ok = load_input_chunk(&self.cluster, &group, inputs, &self.output_sender);
 
             monitoring.record(Event::GroupLoadingEnd(group_id));
 
@@ -70,57 +73,97 @@ impl <InputHandle> Stage for InputLoader<InputHandle>
     }
 }
 
-fn load_input_chunk<T>(driver: &Driver, group: &DpuGroup, chunk: Vec<Vec<InputMemoryTransfer>>,
+fn load_input_chunk<T>(cluster: &Cluster, group: &DpuGroup, chunk: Vec<DpuInputs>,
                        output_sender: &SyncSender<OutputResult<T>>) -> bool {
-    match chunk.iter().max_by_key(|t| t.len()).map(|t| t.len()) {
-        // the None case (empty group) never happens
-        None => true,
-        Some(max_len) =>
-            match do_memory_transfers(driver, group, chunk, max_len) {
-                Err(err) => {
-                    output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap();
-                    false
-                },
-                Ok(_) => {
-                    let mut is_ok = true;
-
-                    for dpu in group.active_dpus() {
-                        match driver.boot(&View::one(dpu.clone())) {
-                            Ok(_) => (),
-                            Err(err) => {
-                                output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap();
-                                is_ok = false;
-                                break;
-                            }
-                        }
-                    }
-
-                    is_ok
-                },
-            },
+    let driver = cluster.driver();
+    let (programs, chunk): (Vec<_>, Vec<_>) = chunk.into_iter().unzip();
+
+    let loading = load_programs(driver, group, &programs)
+        .and_then(|_| do_memory_transfers(cluster, group, chunk));
+
+    if let Err(err) = loading {
+        output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap();
+        return false;
+    }
+
+    for dpu in group.active_dpus() {
+        if let Err(err) = driver.boot(&View::one(*dpu)) {
+            output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap();
+            return false;
+        }
     }
-}
 
-fn do_memory_transfers(driver: &Driver, group: &DpuGroup, mut chunk: Vec<Vec<InputMemoryTransfer>>, max_len: usize) -> Result<(), ClusterError> {
-    let mut memory_transfers = Vec::with_capacity(max_len);
+    true
+}
 
-    for _ in 0..max_len {
-        memory_transfers.push(MemoryTransfer::default());
+fn load_programs(driver: &Driver, group: &DpuGroup, programs: &[Option<Arc<Program>>]) -> Result<(), ClusterError> {
+    for (dpu, program) in group.active_dpus().zip(programs) {
+        if let Some(program) = program {
+            driver.load_if_needed(dpu, program)?;
+        }
     }
 
-    let dpus = group.active_dpus().collect::<Vec<_>>();
+    Ok(())
+}
 
-    for (idx, transfers) in chunk.iter_mut().enumerate() {
-        for (i, transfer) in transfers.iter_mut().enumerate() {
-            let memory_transfer = memory_transfers.get_mut(i).unwrap();
+// Broadcasts are sent once to all the DPUs of the jobs sharing them, after the other inputs: an input
+// overlapping a pending broadcast of its DPU first sends the pending transfers, for the inputs of a job
+// to be written in order.
+fn do_memory_transfers(cluster: &Cluster, group: &DpuGroup, chunk: Vec<Vec<InputMemoryTransfer>>) -> Result<(), ClusterError> {
+    let driver = cluster.driver();
+    let topology = cluster.topology();
+    // held until the transfers are done, for their regions not to be freed or resized meanwhile
+    let allocator = cluster.mram_allocator();
+    let mut memory_transfer = ToDpuTransfer::default().bouncing_unaligned_regions();
+    let mut broadcasts: Vec<(&InputMemoryTransfer, Vec<DpuId>)> = Vec::default();
+
+    for (dpu, transfers) in group.active_dpus().zip(chunk.iter()) {
+        for transfer in transfers.iter() {
+            transfer.check_region(&allocator, *dpu)?;
+
+            if !transfer.broadcast {
+                if broadcasts.iter().any(|(other, dpus)| dpus.contains(dpu) && overlaps(other, transfer)) {
+                    send_pending_transfers(driver, topology, &mut memory_transfer, &mut broadcasts)?;
+                }
+                memory_transfer.add_in_place(*dpu, transfer.offset, transfer.as_slice());
+                continue;
+            }
 
-            memory_transfer.add_in_place(*dpus.get(idx).unwrap().clone(), transfer.offset, transfer.content.as_mut_slice());
+            // joining a broadcast sends the transfer before the next pending ones, which must not overlap it on this DPU
+            let same_broadcast = broadcasts.iter()
+                .rposition(|(other, _)| is_same_broadcast(other, transfer))
+                .filter(|index| !broadcasts[index + 1..].iter().any(|(other, dpus)| dpus.contains(dpu) && overlaps(other, transfer)));
+
+            match same_broadcast {
+                Some(index) => broadcasts[index].1.push(*dpu),
+                None => broadcasts.push((transfer, vec![*dpu])),
+            }
         }
     }
 
-    for memory_transfer in memory_transfers.iter_mut() {
-        driver.copy_to_memory(memory_transfer)?;
+    send_pending_transfers(driver, topology, &mut memory_transfer, &mut broadcasts)
+}
+
+fn send_pending_transfers<'a>(driver: &Driver, topology: (u8, u8, u8), memory_transfer: &mut ToDpuTransfer<'a>,
+                              broadcasts: &mut Vec<(&'a InputMemoryTransfer, Vec<DpuId>)>) -> Result<(), ClusterError> {
+    driver.copy_to_memory(memory_transfer)?;
+    *memory_transfer = ToDpuTransfer::default().bouncing_unaligned_regions();
+
+    for (transfer, dpus) in broadcasts.drain(..) {
+        driver.broadcast_to_memory(&View::from_dpus(&dpus, topology), transfer.offset, transfer.as_slice())?;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+// shared buffers are recognized without comparing their content
+fn is_same_broadcast(first: &InputMemoryTransfer, second: &InputMemoryTransfer) -> bool {
+    first.offset == second.offset
+        && (ptr::eq(first.as_slice(), second.as_slice()) || first.as_slice() == second.as_slice())
+}
+
+fn overlaps(first: &InputMemoryTransfer, second: &InputMemoryTransfer) -> bool {
+    let end = |transfer: &InputMemoryTransfer| transfer.offset as u64 + transfer.as_slice().len() as u64;
+
+    (first.offset as u64) < end(second) && (second.offset as u64) < end(first)
+}
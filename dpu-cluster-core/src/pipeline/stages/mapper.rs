@@ -8,32 +8,37 @@ use std::sync::Arc;
 use crate::pipeline::monitoring::EventMonitor;
 use crate::pipeline::monitoring::Process;
 use std::sync::mpsc::Sender;
-use crate::pipeline::transfer::OutputMemoryTransfer;
 use crate::pipeline::transfer::InputMemoryTransfer;
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use crate::dpu::DpuId;
 use std::hash::Hash;
+use std::marker::PhantomData;
 use crate::pipeline::OutputResult;
 use std::sync::mpsc::SyncSender;
 use crate::pipeline::PipelineError;
 use crate::pipeline::GroupId;
 use crate::cluster::Cluster;
-use crate::memory::MemoryTransfer;
+use crate::memory::ToDpuTransfer;
+use crate::memory::check_mram_bounds;
+use crate::pipeline::stages::GroupTransfers;
 
 struct BaseMapper<InputItem, InputHandle> {
     groups: Vec<DpuGroup>,
     input_receiver: Receiver<InputItem>,
     group_receiver: Receiver<DpuGroup>,
-    transfer_sender: Sender<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(InputHandle, OutputMemoryTransfer)>)>,
+    transfer_sender: Sender<GroupTransfers<InputHandle>>,
     monitoring: EventMonitor,
     // todo: use or remove
+    #[allow(dead_code)]
     shutdown: Arc<Mutex<bool>>
 }
 
 pub struct SimpleMapper<InputItem, InputHandle> {
     base: BaseMapper<InputItem, InputHandle>,
-    get_transfers: Box<dyn Fn(InputItem) -> MemoryTransfers<InputHandle> + Send>
+    cluster: Arc<Cluster>,
+    get_transfers: Box<dyn Fn(InputItem) -> MemoryTransfers<InputHandle> + Send>,
+    output_sender: SyncSender<OutputResult<InputHandle>>
 }
 
 pub struct PersistentMapper<InputItem, InputHandle, FragmentId, FragmentIterator> {
@@ -42,7 +47,11 @@ pub struct PersistentMapper<InputItem, InputHandle, FragmentId, FragmentIterator
     get_transfers: Box<dyn Fn(InputItem) -> (FragmentId, MemoryTransfers<InputHandle>) + Send>,
     output_sender: SyncSender<OutputResult<InputHandle>>,
     mapping: Box<FragmentIterator>,
-    fragment_map: HashMap<FragmentId, (DpuId, GroupId)>,
+    dataset: String,
+    // anonymous datasets are forgotten with their plan
+    is_anonymous: bool,
+    fragment_ids: PhantomData<FragmentId>,
+    group_of: HashMap<DpuId, GroupId>,
     available_groups: HashMap<GroupId, (DpuGroup, HashMap<DpuId, MemoryTransfers<InputHandle>>)>
 }
 
@@ -50,18 +59,23 @@ impl <I, K> SimpleMapper<I, K>
     where I: Send + 'static,
           K: Send + 'static
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(get_transfers: Box<dyn Fn(I) -> MemoryTransfers<K> + Send>,
                groups: Vec<DpuGroup>,
                input_receiver: Receiver<I>,
                group_receiver: Receiver<DpuGroup>,
-               transfer_sender: Sender<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(K, OutputMemoryTransfer)>)>,
+               cluster: Arc<Cluster>,
+               transfer_sender: Sender<GroupTransfers<K>>,
+               output_sender: SyncSender<OutputResult<K>>,
                mut monitoring: EventMonitor,
                shutdown: Arc<Mutex<bool>>) -> Self {
         monitoring.set_process(Process::Mapper);
 
         SimpleMapper {
             base: BaseMapper { groups, input_receiver, group_receiver, transfer_sender, monitoring, shutdown },
-            get_transfers
+            cluster,
+            get_transfers,
+            output_sender
         }
     }
 }
@@ -72,25 +86,37 @@ impl <I, K, D, IT> PersistentMapper<I, K, D, IT>
           D: Eq + Hash + Send + 'static,
           IT: Iterator<Item=(D, InputMemoryTransfer)>
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(get_transfers: Box<dyn Fn(I) -> (D, MemoryTransfers<K>) + Send>,
                groups: Vec<DpuGroup>,
                input_receiver: Receiver<I>,
                group_receiver: Receiver<DpuGroup>,
                cluster: Arc<Cluster>,
-               transfer_sender: Sender<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(K, OutputMemoryTransfer)>)>,
+               transfer_sender: Sender<GroupTransfers<K>>,
                output_sender: SyncSender<OutputResult<K>>,
                mapping: Box<IT>,
+               dataset: Option<String>,
                mut monitoring: EventMonitor,
                shutdown: Arc<Mutex<bool>>) -> Self {
         monitoring.set_process(Process::Mapper);
 
+        let group_of = groups.iter()
+            .flat_map(|group| group.dpus.iter().map(move |(dpu, _)| (*dpu, group.id)))
+            .collect();
+
+        let is_anonymous = dataset.is_none();
+        let dataset = dataset.unwrap_or_else(|| cluster.catalog().anonymous_dataset());
+
         PersistentMapper {
             base: BaseMapper { groups, input_receiver, group_receiver, transfer_sender, monitoring, shutdown },
             cluster,
             get_transfers,
             output_sender,
             mapping,
-            fragment_map: Default::default(),
+            dataset,
+            is_anonymous,
+            fragment_ids: PhantomData,
+            group_of,
             available_groups: Default::default(),
         }
     }
@@ -105,11 +131,12 @@ impl <I, K> Stage for SimpleMapper<I, K>
 
         monitoring.record(Event::ProcessBegin);
 
-        let mut iterator = self.base.input_receiver.iter();
+        let mram_size = self.cluster.driver().rank_description.memories.mram_size;
+        let mut iterator = self.base.input_receiver.iter().peekable();
 
-        while let Some(item) = iterator.next() {
+        while iterator.peek().is_some() {
             monitoring.record(Event::GroupSearchBegin);
-            let mut group = fetch_next_group(&mut self.base.groups, &mut self.base.group_receiver);
+            let mut group = fetch_next_group(&mut self.base.groups, &self.base.group_receiver);
             let group_id = group.id;
             monitoring.record(Event::GroupSearchEnd(group_id));
 
@@ -117,26 +144,34 @@ impl <I, K> Stage for SimpleMapper<I, K>
             let mut inputs = Vec::with_capacity(group_size);
             let mut outputs = Vec::with_capacity(group_size);
 
-            let transfers = (self.get_transfers)(item);
-            inputs.push(transfers.inputs);
-            outputs.push((transfers.key, transfers.output));
-
             while inputs.len() != group_size {
                 match iterator.next() {
                     None => {
                         for (_, is_active) in group.dpus.iter_mut().skip(inputs.len()) {
                             *is_active = false;
                         }
+                        break;
                     },
                     Some(item) => {
                         let transfers = (self.get_transfers)(item);
-                        inputs.push(transfers.inputs);
-                        outputs.push((transfers.key, transfers.output));
+
+                        // rejected jobs leave their DPU to the next ones
+                        match transfers.check_bounds(group.dpus[inputs.len()].0, mram_size) {
+                            Err(err) => self.output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap(),
+                            Ok(()) => {
+                                inputs.push((transfers.program, transfers.inputs));
+                                outputs.push((transfers.key, transfers.output));
+                            },
+                        }
                     }
                 }
             }
 
-            self.base.transfer_sender.send((group, inputs, outputs)).unwrap();
+            if inputs.is_empty() {
+                self.base.groups.push(group);
+            } else {
+                self.base.transfer_sender.send((group, inputs, outputs)).unwrap();
+            }
         }
 
         monitoring.record(Event::ProcessEnd);
@@ -150,11 +185,8 @@ fn fetch_next_group(groups: &mut Vec<DpuGroup>, group_receiver: &Receiver<DpuGro
             // todo: fix the issue where no group may be sent because all have failed
             let mut grp = group_receiver.recv().unwrap();
 
-            loop {
-                match group_receiver.try_recv() {
-                    Ok(other_group) => groups.push(other_group),
-                    Err(_) => break,
-                }
+            while let Ok(other_group) = group_receiver.try_recv() {
+                groups.push(other_group);
             }
 
             for (_, activity) in grp.dpus.iter_mut() {
@@ -174,37 +206,53 @@ impl <I, K, D, IT> Stage for PersistentMapper<I, K, D, IT>
 {
     fn init(&mut self) -> Result<(), PipelineError> {
         let driver = self.cluster.driver();
-
-        for mut group in self.base.groups.iter().cloned() {
-            let group_id = group.id;
-            let mut transfers = Vec::with_capacity(group.dpus.len());
-
-            for (dpu, _) in &group.dpus {
-                match self.mapping.next() {
-                    None => break,
-                    Some((fragment_id, fragment_transfer)) => {
-                        self.fragment_map.insert(fragment_id, (*dpu, group_id));
-                        transfers.push((*dpu, fragment_transfer));
-                    },
-                }
+        let catalog = self.cluster.catalog();
+        let mram_size = driver.rank_description.memories.mram_size;
+        let dataset = &self.dataset;
+
+        // the new fragments go to the DPUs without fragments of the dataset first, not to overwrite the ones
+        // loaded by previous plans
+        let (free_dpus, used_dpus): (Vec<_>, Vec<_>) = self.base.groups.iter()
+            .flat_map(|group| group.dpus.iter().map(|(dpu, _)| *dpu))
+            .partition(|dpu| !catalog.holds_fragments_on(dataset, dpu));
+        let mut transfers = Vec::default();
+
+        for dpu in free_dpus.into_iter().chain(used_dpus) {
+            match self.mapping.next() {
+                None => break,
+                Some((fragment_id, fragment_transfer)) => {
+                    let length = fragment_transfer.as_slice().len();
+                    check_mram_bounds(dpu, fragment_transfer.offset, length, mram_size)?;
+                    transfers.push((dpu, fragment_id, fragment_transfer));
+                },
             }
+        }
 
-            if !transfers.is_empty() {
-                group.dpus.truncate(transfers.len());
+        if !transfers.is_empty() {
+            let mut memory_transfer = ToDpuTransfer::default().bouncing_unaligned_regions();
 
-                let mut memory_transfer = MemoryTransfer::default();
+            for (dpu, _, transfer) in transfers.iter() {
+                memory_transfer.add_in_place(*dpu, transfer.offset, transfer.as_slice());
+            }
 
-                for (dpu, transfer) in transfers.iter_mut() {
-                    memory_transfer.add_in_place(*dpu, transfer.offset, transfer.content.as_mut_slice());
-                }
+            driver.copy_to_memory(&mut memory_transfer)?;
 
-                driver.copy_to_memory(&mut memory_transfer)?;
+            // only the fragments which reached their DPU are recorded
+            for (dpu, fragment_id, transfer) in transfers {
+                catalog.record(dataset, fragment_id, dpu, transfer.offset, transfer.as_slice().len() as u32)?;
+            }
+        }
+
+        // the groups keep every DPU holding a fragment, whichever plan loaded it
+        for mut group in self.base.groups.iter().cloned() {
+            group.dpus.retain(|(dpu, _)| catalog.holds_fragments_on(dataset, dpu));
 
-                self.available_groups.insert(group_id, (group, HashMap::default()));
+            if !group.dpus.is_empty() {
+                self.available_groups.insert(group.id, (group, HashMap::default()));
             }
         }
 
-        if let Some(_) = self.mapping.next() {
+        if self.mapping.next().is_some() {
             Err(PipelineError::TooManyFragments)
         } else {
             Ok(())
@@ -216,22 +264,31 @@ impl <I, K, D, IT> Stage for PersistentMapper<I, K, D, IT>
 
         monitoring.record(Event::ProcessBegin);
 
+        let catalog = self.cluster.catalog();
+        let mram_size = self.cluster.driver().rank_description.memories.mram_size;
+        let dataset = &self.dataset;
+        let group_of = &self.group_of;
         let mut waiting_inputs: HashMap<GroupId, HashMap<DpuId, Vec<MemoryTransfers<K>>>> = Default::default();
 
         for item in self.base.input_receiver {
             let (fragment_id, transfers) = (self.get_transfers)(item);
 
-            match self.fragment_map.get(&fragment_id) {
+            let placement = catalog.locate(dataset, &fragment_id)
+                .and_then(|location| group_of.get(&location.dpu).map(|group_id| (location.dpu, *group_id)))
+                .map(|placement| transfers.check_bounds(placement.0, mram_size).map(|_| placement));
+
+            match placement {
                 None => self.output_sender.send(Err(PipelineError::UnknownFragmentId)).unwrap(),
-                Some((dpu_id, group_id)) => {
-                    match self.available_groups.entry(*group_id) {
+                Some(Err(err)) => self.output_sender.send(Err(PipelineError::InfrastructureError(err))).unwrap(),
+                Some(Ok((dpu_id, group_id))) => {
+                    match self.available_groups.entry(group_id) {
                         Entry::Occupied(mut group_entry) => {
                             let should_launch = {
                                 let (group, dpus) = group_entry.get_mut();
 
-                                let force_launch = match dpus.entry(*dpu_id) {
+                                let force_launch = match dpus.entry(dpu_id) {
                                     Entry::Occupied(_) => {
-                                        add_waiting_input(&mut waiting_inputs, *group_id, *dpu_id, transfers);
+                                        add_waiting_input(&mut waiting_inputs, group_id, dpu_id, transfers);
                                         true
                                     },
                                     Entry::Vacant(dpu_entry) => {
@@ -249,7 +306,7 @@ impl <I, K, D, IT> Stage for PersistentMapper<I, K, D, IT>
                             }
                         },
                         Entry::Vacant(_) => {
-                            add_waiting_input(&mut waiting_inputs, *group_id, *dpu_id, transfers);
+                            add_waiting_input(&mut waiting_inputs, group_id, dpu_id, transfers);
                         },
                     }
                 },
@@ -296,6 +353,10 @@ impl <I, K, D, IT> Stage for PersistentMapper<I, K, D, IT>
             }
         }
 
+        if self.is_anonymous {
+            catalog.remove_dataset(dataset);
+        }
+
         monitoring.record(Event::ProcessEnd);
     }
 }
@@ -309,7 +370,7 @@ fn is_group_complete<T>(group: &DpuGroup, entries: &HashMap<DpuId, T>) -> bool {
 }
 
 fn build_and_launch_group<K>(mut group: DpuGroup, mut dpus: HashMap<DpuId, MemoryTransfers<K>>,
-                             transfer_sender: &Sender<(DpuGroup, Vec<Vec<InputMemoryTransfer>>, Vec<(K, OutputMemoryTransfer)>)>) {
+                             transfer_sender: &Sender<GroupTransfers<K>>) {
     let group_size = group.dpus.len();
     let mut inputs = Vec::with_capacity(group_size);
     let mut outputs = Vec::with_capacity(group_size);
@@ -321,7 +382,7 @@ fn build_and_launch_group<K>(mut group: DpuGroup, mut dpus: HashMap<DpuId, Memor
             },
             Some(transfers) => {
                 *is_active = true;
-                inputs.push(transfers.inputs);
+                inputs.push((transfers.program, transfers.inputs));
                 outputs.push((transfers.key, transfers.output));
             },
         }
@@ -1,9 +1,12 @@
 use crate::dpu::DpuId;
 use crate::pipeline::transfer::OutputMemoryTransfer;
+use crate::pipeline::transfer::InputMemoryTransfer;
 use crate::pipeline::GroupId;
 use crate::pipeline::ThreadHandle;
 use std::thread;
 use crate::pipeline::PipelineError;
+use crate::program::Program;
+use std::sync::Arc;
 
 pub mod initializer;
 pub mod mapper;
@@ -34,4 +37,6 @@ impl DpuGroup {
     }
 }
 
-type GroupJob<K> = (DpuGroup, Vec<(K, OutputMemoryTransfer)>);
\ No newline at end of file
+type GroupJob<K> = (DpuGroup, Vec<(K, OutputMemoryTransfer)>);
+type DpuInputs = (Option<Arc<Program>>, Vec<InputMemoryTransfer>);
+type GroupTransfers<K> = (DpuGroup, Vec<DpuInputs>, Vec<(K, OutputMemoryTransfer)>);
\ No newline at end of file
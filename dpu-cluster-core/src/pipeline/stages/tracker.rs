@@ -29,6 +29,7 @@ pub struct ExecutionTracker<InputHandle> {
     sleep_duration: Option<Duration>,
     monitoring: EventMonitor,
     // todo: use or remove
+    #[allow(dead_code)]
     shutdown: Arc<Mutex<bool>>
 }
 
@@ -67,7 +68,7 @@ impl <InputHandle> Stage for ExecutionTracker<InputHandle>
                     },
                     Err(TryRecvError::Empty) => break,
                     Err(TryRecvError::Disconnected) =>
-                        if jobs.len() == 0 {
+                        if jobs.is_empty() {
                             monitoring.record(Event::ProcessEnd);
                             return
                         } else {
@@ -86,7 +87,7 @@ impl <InputHandle> Stage for ExecutionTracker<InputHandle>
                         monitoring.record(Event::JobExecutionTrackingEnd(group_id));
                         self.finish_sender.send(job).unwrap();
                     },
-                    Ok(RunStatus::Fault(faults)) => {
+                    Ok(RunStatus::Fault(faults)) | Ok(RunStatus::TimedOut(faults)) => {
                         monitoring.record(Event::JobExecutionTrackingEnd(group_id));
                         for faulting_dpu in faults {
                             self.output_sender.send(Err(PipelineError::ExecutionError(faulting_dpu))).unwrap();
@@ -115,7 +116,7 @@ fn fetch_group_status(driver: &Driver, group: &DpuGroup) -> Result<RunStatus, Cl
     let mut global_status = RunStatus::default();
 
     for dpu in group.active_dpus() {
-        let status = driver.fetch_status(&View::one(dpu.clone()))?;
+        let status = driver.fetch_status(&View::one(*dpu))?;
         global_status = global_status.merge_with(&status)
     }
 
@@ -1,14 +1,30 @@
-use core::mem;
+use std::mem;
+use crate::dpu::DpuId;
+use crate::error::ClusterError;
+use crate::memory::MramAllocator;
+use crate::memory::MramRegion;
+use crate::memory::check_mram_bounds;
+use crate::pod::DpuPod;
+use crate::pod::PodVec;
+use crate::program::Program;
+use std::sync::Arc;
 
+// Without program, the job runs on whatever program is resident on its DPU.
 pub struct MemoryTransfers<K> {
     pub inputs: Vec<InputMemoryTransfer>,
     pub output: OutputMemoryTransfer,
+    pub program: Option<Arc<Program>>,
     pub key: K
 }
 
+// The content is only read, so shared buffers (Arc<[u8]>, memory-mapped files) can be sent without a copy.
+// Identical broadcast transfers of the jobs of a batch are sent once to all their DPUs.
 pub struct InputMemoryTransfer {
     pub offset: u32,
-    pub content: Vec<u8>
+    pub content: Box<dyn AsRef<[u8]> + Send>,
+    pub broadcast: bool,
+    // the region the transfer targets, if any
+    region: Option<MramRegion<u8>>
 }
 
 pub struct OutputMemoryTransfer {
@@ -16,26 +32,81 @@ pub struct OutputMemoryTransfer {
     pub length: u32,
 }
 
+impl <K> MemoryTransfers<K> {
+    pub(crate) fn check_bounds(&self, dpu: DpuId, limit: u32) -> Result<(), ClusterError> {
+        for input in &self.inputs {
+            if let Some(region) = &input.region {
+                if !region.dpus().contains(&dpu) {
+                    return Err(ClusterError::UnknownRegion { dpu, region: region.name().to_string() });
+                }
+            }
+            check_mram_bounds(dpu, input.offset, input.as_slice().len(), limit)?;
+        }
+
+        check_mram_bounds(dpu, self.output.offset, self.output.length as usize, limit)
+    }
+}
+
 impl InputMemoryTransfer {
     pub fn from_u8_vec(offset: u32, content: Vec<u8>) -> Self {
-        InputMemoryTransfer { offset, content }
+        InputMemoryTransfer::from_buffer(offset, content)
+    }
+
+    pub fn from_buffer<B: AsRef<[u8]> + Send + 'static>(offset: u32, content: B) -> Self {
+        InputMemoryTransfer { offset, content: Box::new(content), broadcast: false, region: None }
     }
 
-    pub fn from_u32_vec(offset: u32, mut content: Vec<u32>) -> Self {
-        let content = unsafe {
-            let ratio = mem::size_of::<u32>() / mem::size_of::<u8>();
+    pub fn broadcast<B: AsRef<[u8]> + Send + 'static>(offset: u32, content: B) -> Self {
+        InputMemoryTransfer { offset, content: Box::new(content), broadcast: true, region: None }
+    }
+
+    pub fn to_symbol<B: AsRef<[u8]> + Send + 'static>(program: &Program, symbol: &str, offset: u32, content: B) -> Result<Self, ClusterError> {
+        let offset = program.resolve_in_mram(symbol, offset, content.as_ref().len() as u32)?;
 
-            let length = content.len() * ratio;
-            let capacity = content.capacity() * ratio;
-            let ptr = content.as_mut_ptr() as *mut u8;
+        Ok(InputMemoryTransfer::from_buffer(offset, content))
+    }
 
-            // Don't run the destructor for vec32
-            mem::forget(content);
+    // the region may have been freed or resized since the transfer was built
+    pub(crate) fn check_region(&self, allocator: &MramAllocator, dpu: DpuId) -> Result<(), ClusterError> {
+        match &self.region {
+            Some(region) => allocator.check(&dpu, region),
+            None => Ok(()),
+        }
+    }
 
-            // Construct new Vec
-            Vec::from_raw_parts(ptr, length, capacity)
-        };
+    pub fn as_slice(&self) -> &[u8] {
+        (*self.content).as_ref()
+    }
 
-        InputMemoryTransfer { offset, content }
+    pub fn from_u32_vec(offset: u32, content: Vec<u32>) -> Self {
+        InputMemoryTransfer::from_pod_vec(offset, content)
     }
-}
\ No newline at end of file
+
+    pub fn from_pod_vec<T: DpuPod + Send>(offset: u32, content: Vec<T>) -> Self {
+        InputMemoryTransfer::from_buffer(offset, PodVec(content))
+    }
+
+    // the DPU of the job must be one of the DPUs of the region
+    pub fn to_region<T: DpuPod + Send>(allocator: &MramAllocator, region: &MramRegion<T>, content: Vec<T>) -> Result<Self, ClusterError> {
+        for dpu in region.dpus() {
+            allocator.check(dpu, region)?;
+        }
+        region.check_length(mem::size_of_val(content.as_slice()))?;
+
+        let mut transfer = InputMemoryTransfer::from_pod_vec(region.offset(), content);
+        transfer.region = Some(region.as_bytes());
+        Ok(transfer)
+    }
+}
+
+impl OutputMemoryTransfer {
+    pub fn from_symbol(program: &Program, symbol: &str, offset: u32, length: u32) -> Result<Self, ClusterError> {
+        let offset = program.resolve_in_mram(symbol, offset, length)?;
+
+        Ok(OutputMemoryTransfer { offset, length })
+    }
+
+    pub fn from_region<T>(region: &MramRegion<T>) -> Self {
+        OutputMemoryTransfer { offset: region.offset(), length: region.byte_length() }
+    }
+}
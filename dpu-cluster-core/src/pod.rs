@@ -0,0 +1,54 @@
+use std::mem;
+use std::slice;
+
+pub use dpu_pod_derive::DpuPod;
+
+/// Plain-old-data which can be copied as is to and from the DPU memories.
+///
+/// # Safety
+///
+/// Implementors must have a stable layout without padding, and accept any bit pattern.
+/// Prefer `#[derive(DpuPod)]`, which checks both.
+pub unsafe trait DpuPod: Copy + 'static {}
+
+unsafe impl DpuPod for u8 {}
+unsafe impl DpuPod for u16 {}
+unsafe impl DpuPod for u32 {}
+unsafe impl DpuPod for u64 {}
+unsafe impl DpuPod for i8 {}
+unsafe impl DpuPod for i16 {}
+unsafe impl DpuPod for i32 {}
+unsafe impl DpuPod for i64 {}
+unsafe impl DpuPod for f32 {}
+unsafe impl DpuPod for f64 {}
+unsafe impl <T: DpuPod, const N: usize> DpuPod for [T; N] {}
+
+pub fn as_bytes<T: DpuPod>(values: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values)) }
+}
+
+pub fn as_bytes_mut<T: DpuPod>(values: &mut [T]) -> &mut [u8] {
+    unsafe { slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, mem::size_of_val(values)) }
+}
+
+// The bytes are copied: a byte buffer is not necessarily aligned for T. Trailing bytes are ignored.
+pub fn from_bytes<T: DpuPod>(bytes: &[u8]) -> Vec<T> {
+    let length = bytes.len() / mem::size_of::<T>().max(1);
+    let mut values = Vec::<T>::with_capacity(length);
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), values.as_mut_ptr() as *mut u8, length * mem::size_of::<T>());
+        values.set_len(length);
+    }
+
+    values
+}
+
+// Owns typed values while exposing them as bytes, for the pipeline transfers
+pub(crate) struct PodVec<T>(pub(crate) Vec<T>);
+
+impl <T: DpuPod> AsRef<[u8]> for PodVec<T> {
+    fn as_ref(&self) -> &[u8] {
+        as_bytes(&self.0)
+    }
+}
@@ -1,17 +1,81 @@
 use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use dpu_sys::DpuRankDescription;
+use crate::error::ClusterError;
 
 type IramAddress = u16;
 type WramAddress = u32;
+type MramAddress = u32;
 
 type Instruction = u64;
 type WramData = u32;
 
+// The DPU linker places each memory in its own address space.
+pub const WRAM_ADDRESS_SPACE: u32 = 0x0000_0000;
+pub const MRAM_ADDRESS_SPACE: u32 = 0x0800_0000;
+pub const IRAM_ADDRESS_SPACE: u32 = 0x8000_0000;
+
+const INSTRUCTION_SIZE: u32 = 8;
+const WRAM_DATA_SIZE: u32 = 4;
+
+static NEXT_PROGRAM_ID: AtomicU64 = AtomicU64::new(0);
+
 pub struct Program {
-    pub iram_sections: HashMap<IramAddress, Vec<Instruction>>,
-    pub wram_sections: HashMap<WramAddress, Vec<WramData>>,
+    iram_sections: HashMap<IramAddress, Vec<Instruction>>,
+    wram_sections: HashMap<WramAddress, Vec<WramData>>,
+    mram_sections: HashMap<MramAddress, Vec<u8>>,
+    symbols: HashMap<String, Symbol>,
+    id: ProgramId,
     binary_file: Option<String>
 }
 
+// Identifies a program instance, to know which one is resident on a DPU
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ProgramId(u64);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Symbol {
+    pub address: u32,
+    pub size: u32
+}
+
+// Byte addresses inside the memory holding a symbol
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SymbolAddress {
+    Iram(u32),
+    Wram(u32),
+    Mram(u32)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Memory {
+    Iram,
+    Wram,
+    Mram
+}
+
+// Section offsets and lengths are expressed in instructions for the IRAM, words for the WRAM
+// and bytes for the MRAM, as the memory sizes of the rank description.
+#[derive(Debug, Clone)]
+pub enum ProgramError {
+    Io(io::ErrorKind),
+    InvalidElf(&'static str),
+    SectionTooLarge { memory: Memory, offset: u32, length: usize },
+    SectionOutOfBounds { memory: Memory, offset: u32, length: u32, size: u32 },
+    OverlappingSections { memory: Memory, first: u32, second: u32 }
+}
+
+impl From<io::Error> for ProgramError {
+    fn from(err: io::Error) -> Self {
+        ProgramError::Io(err.kind())
+    }
+}
+
 impl Program {
     pub fn new(iram: Vec<Instruction>, wram: Vec<WramData>, binary_file: Option<String>) -> Program {
         let mut iram_sections = HashMap::default();
@@ -19,13 +83,290 @@ impl Program {
         iram_sections.insert(0, iram);
         wram_sections.insert(0, wram);
 
-        Program { iram_sections, wram_sections, binary_file }
+        Program { iram_sections, wram_sections, mram_sections: Default::default(), symbols: Default::default(), id: ProgramId::next(), binary_file }
+    }
+
+    pub fn from_sections(iram_sections: HashMap<IramAddress, Vec<Instruction>>,
+                         wram_sections: HashMap<WramAddress, Vec<WramData>>,
+                         mram_sections: HashMap<MramAddress, Vec<u8>>) -> Program {
+        Program { iram_sections, wram_sections, mram_sections, symbols: Default::default(), id: ProgramId::next(), binary_file: None }
     }
 
     pub fn new_raw(iram: Vec<u8>, wram: Vec<u8>) -> Program {
-        let iram = iram.chunks(8).map(|chunk| chunk.iter().fold((0 as u64, 0), |(acc, i), b| (acc | ((*b as u64) << i), i + 8))).map(|(x, _)| x).collect();
-        let wram = wram.chunks(4).map(|chunk| chunk.iter().fold((0 as u32, 0), |(acc, i), b| (acc | ((*b as u32) << i), i + 8))).map(|(x, _)| x).collect();
+        Program::new(to_instructions(&iram), to_wram_data(&wram), None)
+    }
+
+    pub fn from_elf<P: AsRef<Path>>(path: P) -> Result<Program, ProgramError> {
+        let path = path.as_ref();
+        let content = fs::read(path)?;
+        let elf = Elf::parse(&content)?;
 
-        Program::new(iram, wram, None)
+        let mut iram_sections = HashMap::default();
+        let mut wram_sections = HashMap::default();
+        let mut mram_sections = HashMap::default();
+
+        for section in elf.sections.iter().filter(|section| section.is_loadable()) {
+            let data = elf.content_of(section)?;
+
+            if section.address >= IRAM_ADDRESS_SPACE {
+                let offset = section.address - IRAM_ADDRESS_SPACE;
+                if !offset.is_multiple_of(INSTRUCTION_SIZE) {
+                    return Err(ProgramError::InvalidElf("misaligned IRAM section"));
+                }
+                let offset = (offset / INSTRUCTION_SIZE).try_into().map_err(|_| ProgramError::InvalidElf("IRAM section out of range"))?;
+                iram_sections.insert(offset, to_instructions(data));
+            } else if section.address >= MRAM_ADDRESS_SPACE {
+                mram_sections.insert(section.address - MRAM_ADDRESS_SPACE, data.to_vec());
+            } else {
+                if !section.address.is_multiple_of(WRAM_DATA_SIZE) {
+                    return Err(ProgramError::InvalidElf("misaligned WRAM section"));
+                }
+                wram_sections.insert(section.address / WRAM_DATA_SIZE, to_wram_data(data));
+            }
+        }
+
+        let symbols = elf.symbols()?;
+
+        Ok(Program { iram_sections, wram_sections, mram_sections, symbols, id: ProgramId::next(), binary_file: Some(path.to_string_lossy().into_owned()) })
     }
-}
\ No newline at end of file
+
+    pub fn validate(&self, description: &DpuRankDescription) -> Result<(), ClusterError> {
+        let memories = &description.memories;
+
+        validate_sections(Memory::Iram, self.iram_sections.iter().map(|(offset, instructions)| (*offset as u32, instructions.len())), memories.iram_size as u32)?;
+        validate_sections(Memory::Wram, self.wram_sections.iter().map(|(offset, words)| (*offset, words.len())), memories.wram_size)?;
+        validate_sections(Memory::Mram, self.mram_sections.iter().map(|(offset, data)| (*offset, data.len())), memories.mram_size)?;
+
+        Ok(())
+    }
+
+    pub fn id(&self) -> ProgramId {
+        self.id
+    }
+
+    pub fn binary_file(&self) -> Option<&str> {
+        self.binary_file.as_deref()
+    }
+
+    // the sections are read-only: the id of a program identifies its content
+    pub fn iram_sections(&self) -> &HashMap<IramAddress, Vec<Instruction>> {
+        &self.iram_sections
+    }
+
+    pub fn wram_sections(&self) -> &HashMap<WramAddress, Vec<WramData>> {
+        &self.wram_sections
+    }
+
+    pub fn mram_sections(&self) -> &HashMap<MramAddress, Vec<u8>> {
+        &self.mram_sections
+    }
+
+    pub fn symbols(&self) -> &HashMap<String, Symbol> {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+
+    pub fn resolve(&self, symbol: &str, offset: u32, length: u32) -> Result<SymbolAddress, ClusterError> {
+        let Symbol { address, size } = *self.symbol(symbol).ok_or_else(|| ClusterError::UnknownSymbol(symbol.to_string()))?;
+
+        if (offset as u64) + (length as u64) > (size as u64) {
+            return Err(ClusterError::SymbolOverflow { symbol: symbol.to_string(), offset, length, size });
+        }
+
+        let address = address.checked_add(offset)
+            .ok_or_else(|| ClusterError::SymbolOverflow { symbol: symbol.to_string(), offset, length, size })?;
+
+        if address >= IRAM_ADDRESS_SPACE {
+            Ok(SymbolAddress::Iram(address - IRAM_ADDRESS_SPACE))
+        } else if address >= MRAM_ADDRESS_SPACE {
+            Ok(SymbolAddress::Mram(address - MRAM_ADDRESS_SPACE))
+        } else {
+            Ok(SymbolAddress::Wram(address - WRAM_ADDRESS_SPACE))
+        }
+    }
+
+    pub fn resolve_in_mram(&self, symbol: &str, offset: u32, length: u32) -> Result<u32, ClusterError> {
+        match self.resolve(symbol, offset, length)? {
+            SymbolAddress::Mram(address) => Ok(address),
+            _ => Err(ClusterError::InvalidSymbolLocation(symbol.to_string())),
+        }
+    }
+}
+
+impl ProgramId {
+    fn next() -> Self {
+        ProgramId(NEXT_PROGRAM_ID.fetch_add(1, Ordering::SeqCst))
+    }
+}
+
+fn validate_sections<I>(memory: Memory, sections: I, size: u32) -> Result<(), ClusterError>
+    where I: Iterator<Item=(u32, usize)>
+{
+    let mut ranges = Vec::default();
+
+    for (offset, length) in sections {
+        // the IRAM transfers take a 16-bit length
+        let max_length = if memory == Memory::Iram { u16::MAX as usize } else { u32::MAX as usize };
+        if length > max_length {
+            return Err(ClusterError::InvalidProgram(ProgramError::SectionTooLarge { memory, offset, length }));
+        }
+
+        let length = length as u32;
+        if (offset as u64) + (length as u64) > (size as u64) {
+            return Err(ClusterError::InvalidProgram(ProgramError::SectionOutOfBounds { memory, offset, length, size }));
+        }
+
+        ranges.push((offset, offset + length));
+    }
+
+    ranges.sort();
+    for pair in ranges.windows(2) {
+        let ((first, first_end), (second, _)) = (pair[0], pair[1]);
+        if second < first_end {
+            return Err(ClusterError::InvalidProgram(ProgramError::OverlappingSections { memory, first, second }));
+        }
+    }
+
+    Ok(())
+}
+
+fn to_instructions(bytes: &[u8]) -> Vec<Instruction> {
+    bytes.chunks(8).map(|chunk| chunk.iter().fold((0u64, 0), |(acc, i), b| (acc | ((*b as u64) << i), i + 8))).map(|(x, _)| x).collect()
+}
+
+fn to_wram_data(bytes: &[u8]) -> Vec<WramData> {
+    bytes.chunks(4).map(|chunk| chunk.iter().fold((0u32, 0), |(acc, i), b| (acc | ((*b as u32) << i), i + 8))).map(|(x, _)| x).collect()
+}
+
+// DPU binaries are 32-bit little endian ELF files. Only the section headers and the symbol table
+// are needed to load a program.
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+const ELF_CLASS_32: u8 = 1;
+const ELF_DATA_LITTLE_ENDIAN: u8 = 1;
+const ELF_MACHINE_DPU: u16 = 0xf5;
+const ELF_HEADER_SIZE: usize = 52;
+const SECTION_HEADER_SIZE: usize = 40;
+const SYMBOL_SIZE: usize = 16;
+
+const SECTION_TYPE_PROGBITS: u32 = 1;
+const SECTION_TYPE_SYMTAB: u32 = 2;
+const SECTION_FLAG_ALLOC: u32 = 2;
+
+const SYMBOL_TYPE_SECTION: u8 = 3;
+const SYMBOL_TYPE_FILE: u8 = 4;
+
+struct Elf<'a> {
+    content: &'a [u8],
+    sections: Vec<Section>
+}
+
+struct Section {
+    kind: u32,
+    flags: u32,
+    address: u32,
+    offset: u32,
+    size: u32,
+    link: u32
+}
+
+impl Section {
+    fn is_loadable(&self) -> bool {
+        self.kind == SECTION_TYPE_PROGBITS && (self.flags & SECTION_FLAG_ALLOC) != 0 && self.size != 0
+    }
+}
+
+impl <'a> Elf<'a> {
+    fn parse(content: &'a [u8]) -> Result<Self, ProgramError> {
+        if content.len() < ELF_HEADER_SIZE || &content[0..4] != ELF_MAGIC {
+            return Err(ProgramError::InvalidElf("not an ELF file"));
+        }
+        if content[4] != ELF_CLASS_32 || content[5] != ELF_DATA_LITTLE_ENDIAN {
+            return Err(ProgramError::InvalidElf("not a 32-bit little endian ELF file"));
+        }
+        if read_u16(content, 18)? != ELF_MACHINE_DPU {
+            return Err(ProgramError::InvalidElf("not a DPU binary"));
+        }
+
+        let section_table = read_u32(content, 32)? as usize;
+        let section_entry_size = read_u16(content, 46)? as usize;
+        let nr_of_sections = read_u16(content, 48)? as usize;
+
+        if nr_of_sections != 0 && section_entry_size < SECTION_HEADER_SIZE {
+            return Err(ProgramError::InvalidElf("invalid section header size"));
+        }
+
+        let sections = (0..nr_of_sections)
+            .map(|idx| {
+                let header = section_table + idx * section_entry_size;
+                Ok(Section {
+                    kind: read_u32(content, header + 4)?,
+                    flags: read_u32(content, header + 8)?,
+                    address: read_u32(content, header + 12)?,
+                    offset: read_u32(content, header + 16)?,
+                    size: read_u32(content, header + 20)?,
+                    link: read_u32(content, header + 24)?
+                })
+            })
+            .collect::<Result<_, ProgramError>>()?;
+
+        Ok(Elf { content, sections })
+    }
+
+    fn content_of(&self, section: &Section) -> Result<&'a [u8], ProgramError> {
+        let start = section.offset as usize;
+        let end = start + section.size as usize;
+
+        self.content.get(start..end).ok_or(ProgramError::InvalidElf("truncated section"))
+    }
+
+    fn symbols(&self) -> Result<HashMap<String, Symbol>, ProgramError> {
+        let mut symbols = HashMap::default();
+
+        for table in self.sections.iter().filter(|section| section.kind == SECTION_TYPE_SYMTAB) {
+            let entries = self.content_of(table)?;
+            let names = self.sections.get(table.link as usize).ok_or(ProgramError::InvalidElf("invalid symbol string table"))?;
+            let names = self.content_of(names)?;
+
+            for entry in entries.chunks_exact(SYMBOL_SIZE) {
+                let kind = entry[12] & 0xf;
+                if kind == SYMBOL_TYPE_SECTION || kind == SYMBOL_TYPE_FILE {
+                    continue;
+                }
+
+                let name = read_string(names, read_u32(entry, 0)? as usize)?;
+                if name.is_empty() {
+                    continue;
+                }
+
+                let symbol = Symbol { address: read_u32(entry, 4)?, size: read_u32(entry, 8)? };
+                symbols.insert(name.to_string(), symbol);
+            }
+        }
+
+        Ok(symbols)
+    }
+}
+
+fn read_u16(content: &[u8], offset: usize) -> Result<u16, ProgramError> {
+    content.get(offset..offset + 2)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(ProgramError::InvalidElf("truncated file"))
+}
+
+fn read_u32(content: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    content.get(offset..offset + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(ProgramError::InvalidElf("truncated file"))
+}
+
+fn read_string(content: &[u8], offset: usize) -> Result<&str, ProgramError> {
+    let bytes = content.get(offset..).ok_or(ProgramError::InvalidElf("invalid symbol name"))?;
+    let length = bytes.iter().position(|b| *b == 0).ok_or(ProgramError::InvalidElf("invalid symbol name"))?;
+
+    std::str::from_utf8(&bytes[..length]).map_err(|_| ProgramError::InvalidElf("invalid symbol name"))
+}
@@ -1,7 +1,9 @@
 use crate::dpu::DpuId;
 
+#[derive(Default)]
 pub enum Selection<T> {
     All,
+    #[default]
     None,
     Some(Vec<T>)
 }
@@ -11,12 +13,6 @@ pub enum FastSelection<T> {
     Normal(Selection<T>)
 }
 
-impl <T> Default for Selection<T> {
-    fn default() -> Self {
-        Selection::None
-    }
-}
-
 impl <T> Default for FastSelection<T> {
     fn default() -> Self {
         FastSelection::Normal(Default::default())
@@ -34,4 +30,43 @@ impl View {
     pub fn one(dpu: DpuId) -> View {
         View(FastSelection::Fast(dpu))
     }
-}
\ No newline at end of file
+
+    pub fn from_dpus(dpus: &[DpuId], topology: (u8, u8, u8)) -> View {
+        let (nr_of_ranks, nr_of_slices, nr_of_dpus_per_slice) = topology;
+
+        if let [dpu] = dpus {
+            return View::one(*dpu);
+        }
+
+        let mut selected = vec![vec![Vec::default(); nr_of_slices as usize]; nr_of_ranks as usize];
+        for dpu in dpus {
+            let (rank, slice, _) = dpu.members();
+            selected[rank as usize][slice as usize].push(*dpu);
+        }
+
+        let is_full = |dpus: &Vec<DpuId>| dpus.len() == nr_of_dpus_per_slice as usize;
+        let ranks = selected.into_iter()
+            .map(|slices| {
+                if slices.iter().all(is_full) {
+                    Selection::All
+                } else if slices.iter().all(Vec::is_empty) {
+                    Selection::None
+                } else {
+                    Selection::Some(slices.into_iter()
+                        .map(|dpus| match dpus.len() {
+                            0 => Selection::None,
+                            _ if is_full(&dpus) => Selection::All,
+                            _ => Selection::Some(dpus)
+                        })
+                        .collect())
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if ranks.iter().all(|rank| matches!(rank, Selection::All)) {
+            View::all()
+        } else {
+            View(FastSelection::Normal(Selection::Some(ranks)))
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::memory::MramAllocator;
+use dpu_cluster_core::memory::MramRegion;
+use dpu_cluster_core::memory::ToDpuTransfer;
+use dpu_cluster_core::pipeline::transfer::InputMemoryTransfer;
+use dpu_cluster_core::pipeline::transfer::OutputMemoryTransfer;
+
+const MRAM_SIZE: u32 = 1024;
+
+#[test]
+fn regions_are_allocated_first_fit_and_aligned() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpu = DpuId::new(0, 0, 0);
+
+    let first = allocator.allocate::<u8>(dpu, "first", 3)?;
+    let second = allocator.allocate::<u32>(dpu, "second", 4)?;
+    let third = allocator.allocate::<u64>(dpu, "third", 2)?;
+
+    assert_eq!((0, 3), (first.offset(), first.byte_length()));
+    assert_eq!((8, 4), (second.offset(), second.len()));
+    assert_eq!(24, third.offset());
+
+    allocator.free(&dpu, "second")?;
+    let fourth = allocator.allocate::<u8>(dpu, "fourth", 16)?;
+    assert_eq!(8, fourth.offset());
+
+    assert_eq!(Some(24), allocator.region::<u64>(&dpu, "third").map(|region| region.offset()));
+    assert!(allocator.region::<u64>(&dpu, "second").is_none());
+    assert!(allocator.region::<u64>(&DpuId::new(0, 0, 1), "third").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn allocations_are_bounded_by_the_mram() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpu = DpuId::new(0, 0, 0);
+
+    allocator.allocate::<u8>(dpu, "head", 512)?;
+
+    match allocator.allocate::<u8>(dpu, "head", 8) {
+        Err(ClusterError::RegionAlreadyAllocated { region, .. }) => assert_eq!("head", region),
+        other => panic!("unexpected allocation: {:?}", other),
+    }
+
+    match allocator.allocate::<u64>(dpu, "tail", 65) {
+        Err(ClusterError::NotEnoughMram { requested, largest_free_block, .. }) => assert_eq!((520, 512), (requested, largest_free_block)),
+        other => panic!("unexpected allocation: {:?}", other),
+    }
+
+    match allocator.free(&dpu, "tail") {
+        Err(ClusterError::UnknownRegion { region, .. }) => assert_eq!("tail", region),
+        other => panic!("unexpected free: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn regions_grow_in_place_or_move() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpu = DpuId::new(0, 0, 0);
+
+    allocator.allocate::<u8>(dpu, "first", 8)?;
+    allocator.allocate::<u8>(dpu, "second", 8)?;
+    allocator.free(&dpu, "first")?;
+
+    let shrunk = allocator.resize::<u8>(&dpu, "second", 4)?;
+    let grown = allocator.resize::<u8>(&dpu, "second", 64)?;
+    assert_eq!((8, 8), (shrunk.offset(), grown.offset()));
+
+    let third = allocator.allocate::<u8>(dpu, "third", 16)?;
+    let moved = allocator.resize::<u8>(&dpu, "second", 128)?;
+    assert_eq!((72, 88), (third.offset(), moved.offset()));
+    assert_eq!(Some(88), allocator.region::<u8>(&dpu, "second").map(|region| region.offset()));
+
+    Ok(())
+}
+
+#[test]
+fn usage_reports_fragmentation() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpu = DpuId::new(0, 0, 0);
+
+    let usage = allocator.usage(&dpu);
+    assert_eq!((0, MRAM_SIZE, MRAM_SIZE, 0), (usage.used, usage.free, usage.largest_free_block, usage.nr_of_regions));
+    assert_eq!(0.0, usage.fragmentation());
+
+    for idx in 0..4 {
+        allocator.allocate::<u8>(dpu, &format!("region{}", idx), 256)?;
+    }
+    allocator.free(&dpu, "region0")?;
+    allocator.free(&dpu, "region2")?;
+
+    let usage = allocator.usage(&dpu);
+    assert_eq!((512, 512, 256, 2), (usage.used, usage.free, usage.largest_free_block, usage.nr_of_regions));
+    assert_eq!(0.5, usage.fragmentation());
+
+    Ok(())
+}
+
+#[test]
+fn shared_regions_have_the_same_offset_on_every_dpu() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpus = vec![DpuId::new(0, 0, 0), DpuId::new(0, 0, 1)];
+
+    allocator.allocate::<u8>(dpus[1], "private", 16)?;
+    let shared: MramRegion<u32> = allocator.allocate_on(&dpus, "shared", 4)?;
+
+    assert_eq!(16, shared.offset());
+    assert_eq!(Some(16), allocator.region::<u32>(&dpus[0], "shared").map(|region| region.offset()));
+
+    Ok(())
+}
+
+#[test]
+fn regions_bound_the_transfers() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let dpu = DpuId::new(0, 0, 0);
+    let region = allocator.allocate::<u32>(dpu, "values", 2)?;
+
+    assert!(ToDpuTransfer::default().add_in_region(dpu, &allocator, &region, [1u32, 2].as_ref()).is_ok());
+    assert!(InputMemoryTransfer::to_region(&allocator, &region, vec![1u32, 2]).is_ok());
+
+    match InputMemoryTransfer::to_region(&allocator, &region, vec![1u32, 2, 3]) {
+        Err(ClusterError::RegionOverflow { region, length, size }) => assert_eq!(("values", 12, 8), (region.as_str(), length, size)),
+        _ => panic!("the transfer should overflow the region"),
+    }
+
+    let output = OutputMemoryTransfer::from_region(&region);
+    assert_eq!((0, 8), (output.offset, output.length));
+
+    Ok(())
+}
+
+#[test]
+fn stale_and_foreign_regions_are_rejected() -> Result<(), ClusterError> {
+    let mut allocator = MramAllocator::new(MRAM_SIZE);
+    let (dpu, other) = (DpuId::new(0, 0, 0), DpuId::new(0, 0, 1));
+    let resized = allocator.allocate::<u32>(dpu, "resized", 2)?;
+    let freed = allocator.allocate::<u32>(dpu, "freed", 2)?;
+    allocator.allocate::<u32>(other, "resized", 2)?;
+
+    allocator.resize::<u32>(&dpu, "resized", 64)?;
+    allocator.free(&dpu, "freed")?;
+
+    for region in &[&resized, &freed] {
+        match ToDpuTransfer::default().add_in_region(dpu, &allocator, region, [1u32, 2].as_ref()) {
+            Err(ClusterError::UnknownRegion { region: name, .. }) => assert_eq!(region.name(), name),
+            _ => panic!("the region is not allocated anymore"),
+        }
+        assert!(InputMemoryTransfer::to_region(&allocator, region, vec![1u32, 2]).is_err());
+    }
+
+    let current = allocator.region::<u32>(&dpu, "resized").unwrap();
+    assert!(ToDpuTransfer::default().add_in_region(dpu, &allocator, &current, [1u32, 2].as_ref()).is_ok());
+    match ToDpuTransfer::default().add_in_region(other, &allocator, &current, [1u32, 2].as_ref()) {
+        Err(ClusterError::UnknownRegion { dpu, .. }) => assert_eq!(other, dpu),
+        _ => panic!("the region is allocated on another DPU"),
+    }
+
+    Ok(())
+}
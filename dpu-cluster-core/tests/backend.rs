@@ -0,0 +1,388 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::ThreadId;
+use dpu_sys::DpuError;
+use dpu_sys::DpuTarget;
+use dpu_sys::DpuRankDescription;
+use dpu_sys::DpuDebugContext;
+use dpu_sys::DpuTopology;
+use dpu_sys::RankBitmap;
+use dpu_cluster_core::backend::RankAllocator;
+use dpu_cluster_core::backend::RankBackend;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::dpu::DpuId;
+use std::time::Duration;
+use dpu_cluster_core::driver::CancelToken;
+use dpu_cluster_core::driver::RunOptions;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::driver::Verification;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::fault::FaultCause;
+use dpu_cluster_core::lease::LeasePolicy;
+use dpu_cluster_core::memory::MemoryTransferRankEntry;
+use dpu_cluster_core::memory::ToDpuTransfer;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::view::View;
+
+const NR_OF_SLICES: u8 = 2;
+const NR_OF_MEMBERS: u8 = 4;
+const NR_OF_THREADS: u8 = 2;
+const NR_OF_REGISTERS: u8 = 4;
+const IRAM_SIZE: u16 = 1 << 12;
+const WRAM_SIZE: u32 = 1 << 14;
+const MRAM_SIZE: u32 = 1 << 20;
+
+#[derive(Debug, Default)]
+struct FakeRank {
+    calls: Arc<Mutex<Vec<&'static str>>>,
+    threads: Arc<Mutex<HashSet<ThreadId>>>,
+    faulting_member: Option<u8>,
+    running_member: Option<u8>,
+    // the threads raising a breakpoint, a DMA fault and a memory fault
+    #[cfg_attr(not(feature = "mock"), allow(dead_code))]
+    raised_faults: [Option<u8>; 3]
+}
+
+#[derive(Default)]
+struct FakeAllocator {
+    calls: Arc<Mutex<Vec<&'static str>>>,
+    threads: Arc<Mutex<HashSet<ThreadId>>>,
+    faulting_member: Option<u8>,
+    running_member: Option<u8>,
+    // the threads raising a breakpoint, a DMA fault and a memory fault
+    raised_faults: [Option<u8>; 3]
+}
+
+fn topology() -> DpuTopology {
+    DpuTopology { nr_of_control_interfaces: NR_OF_SLICES, nr_of_dpus_per_control_interface: NR_OF_MEMBERS }
+}
+
+impl FakeRank {
+    fn record(&self, call: &'static str) -> Result<(), DpuError> {
+        self.calls.lock().unwrap().push(call);
+        self.threads.lock().unwrap().insert(thread::current().id());
+        Ok(())
+    }
+}
+
+impl RankBackend for FakeRank {
+    fn reset_all(&self) -> Result<(), DpuError> { self.record("reset_all") }
+    fn reset_dpu(&self, _: u8, _: u8) -> Result<(), DpuError> { self.record("reset_dpu") }
+
+    fn launch_thread_on_all(&self, _: u8, _: bool) -> Result<RankBitmap, DpuError> {
+        self.record("launch_thread_on_all").map(|_| RankBitmap::for_topology(&topology()))
+    }
+
+    fn launch_thread_on_dpu(&self, _: u8, _: u8, _: u8, _: bool) -> Result<bool, DpuError> {
+        self.record("launch_thread_on_dpu").map(|_| false)
+    }
+
+    fn poll_all(&self) -> Result<(RankBitmap, RankBitmap), DpuError> {
+        let mut is_running = RankBitmap::for_topology(&topology());
+        let mut is_in_fault = RankBitmap::for_topology(&topology());
+        if let Some(member) = self.faulting_member {
+            is_running.set(1, member);
+            is_in_fault.set(1, member);
+        }
+        if let Some(member) = self.running_member {
+            is_running.set(1, member);
+        }
+        self.record("poll_all").map(|_| (is_running, is_in_fault))
+    }
+
+    fn poll_dpu(&self, slice_id: u8, member_id: u8) -> Result<(bool, bool), DpuError> {
+        self.record("poll_dpu").map(|_| (slice_id == 1 && self.running_member == Some(member_id), false))
+    }
+
+    fn fault_all(&self) -> Result<(), DpuError> { self.record("fault_all") }
+    fn fault_dpu(&self, _: u8, _: u8) -> Result<(), DpuError> { self.record("fault_dpu") }
+
+    fn copy_to_irams(&self, _: &[u64], _: u16) -> Result<(), DpuError> { self.record("copy_to_irams") }
+    fn copy_to_iram(&self, _: u8, _: u8, _: &[u64], _: u16) -> Result<(), DpuError> { self.record("copy_to_iram") }
+    fn copy_from_iram(&self, _: u8, _: u8, _: &mut [u64], _: u16) -> Result<(), DpuError> { self.record("copy_from_iram") }
+    fn copy_to_wrams(&self, _: &[u32], _: u32) -> Result<(), DpuError> { self.record("copy_to_wrams") }
+    fn copy_to_wram(&self, _: u8, _: u8, _: &[u32], _: u32) -> Result<(), DpuError> { self.record("copy_to_wram") }
+    fn copy_from_wram(&self, _: u8, _: u8, _: &mut [u32], _: u32) -> Result<(), DpuError> { self.record("copy_from_wram") }
+    fn copy_to_mram(&self, _: u8, _: u8, _: &[u8], _: u32) -> Result<(), DpuError> { self.record("copy_to_mram") }
+    fn copy_from_mram(&self, _: u8, _: u8, _: &mut [u8], _: u32) -> Result<(), DpuError> { self.record("copy_from_mram") }
+    fn copy_to_mrams(&self, _: &mut MemoryTransferRankEntry<&[u8]>) -> Result<(), DpuError> { self.record("copy_to_mrams") }
+    fn copy_from_mrams(&self, _: &mut MemoryTransferRankEntry<&mut [u8]>) -> Result<(), DpuError> { self.record("copy_from_mrams") }
+
+    fn extract_context_from_dpu(&self, _: u8, _: u8, _: &mut DpuDebugContext) -> Result<(), DpuError> { self.record("extract_context_from_dpu") }
+    fn initialize_fault_process_for_dpu(&self, _: u8, _: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
+        context.pcs[1] = 0x42;
+        context.scheduling[1] = 0;
+        context.registers[NR_OF_REGISTERS as usize..].copy_from_slice(&[1, 2, 3, 4]);
+        #[cfg(feature = "mock")]
+        context.set_faults(self.raised_faults[0], self.raised_faults[1], self.raised_faults[2]);
+        self.record("initialize_fault_process_for_dpu")
+    }
+    fn execute_thread_step_on_dpu(&self, _: u8, _: u8, _: u8, _: &mut DpuDebugContext) -> Result<(), DpuError> { self.record("execute_thread_step_on_dpu") }
+    fn finalize_fault_process_for_dpu(&self, _: u8, _: u8, _: &mut DpuDebugContext) -> Result<(), DpuError> { self.record("finalize_fault_process_for_dpu") }
+}
+
+impl RankAllocator for FakeAllocator {
+    fn find_available_ranks_for(&self, target: &DpuTarget, nr_of_ranks_needed: Option<usize>) -> Result<Vec<DpuRankDescription>, DpuError> {
+        (0..nr_of_ranks_needed.unwrap_or(2).min(2)).map(|_| self.get_description_for(target)).collect()
+    }
+
+    fn get_description_for(&self, _: &DpuTarget) -> Result<DpuRankDescription, DpuError> {
+        let mut description = DpuRankDescription::default();
+        description.topology = topology();
+        description.info.nr_of_threads = NR_OF_THREADS;
+        description.info.nr_of_work_registers_per_thread = NR_OF_REGISTERS;
+        description.memories.iram_size = IRAM_SIZE;
+        description.memories.wram_size = WRAM_SIZE;
+        description.memories.mram_size = MRAM_SIZE;
+        Ok(description)
+    }
+
+    fn nr_of_ranks_for(&self, _: &DpuTarget) -> usize {
+        2
+    }
+
+    fn allocate_for(&self, _: &DpuTarget) -> Result<Box<dyn RankBackend>, DpuError> {
+        Ok(Box::new(FakeRank { calls: self.calls.clone(), threads: self.threads.clone(), faulting_member: self.faulting_member, running_member: self.running_member, raised_faults: self.raised_faults }))
+    }
+}
+
+#[test]
+fn cluster_is_built_on_the_given_backend() -> Result<(), ClusterError> {
+    let calls = Arc::new(Mutex::new(Vec::default()));
+    let allocator = FakeAllocator { calls: calls.clone(), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(12), &allocator)?;
+
+    assert_eq!((2, NR_OF_SLICES, NR_OF_MEMBERS), cluster.topology());
+
+    match cluster.driver().run(&View::all())? {
+        RunStatus::Idle => (),
+        _ => panic!("the fake ranks are never running"),
+    }
+
+    assert_eq!(vec!["reset_all", "reset_all", "launch_thread_on_all", "launch_thread_on_all", "poll_all", "poll_all"],
+               *calls.lock().unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn faults_reported_by_the_backend_are_forwarded() -> Result<(), ClusterError> {
+    let allocator = FakeAllocator { faulting_member: Some(3), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(8), &allocator)?;
+
+    match cluster.driver().fetch_status(&View::all())? {
+        RunStatus::Fault(dpus) => assert_eq!(vec![DpuId::new(0, 1, 3)], dpus),
+        _ => panic!("the fake rank should be in fault"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn fault_contexts_are_decoded() -> Result<(), ClusterError> {
+    let allocator = FakeAllocator { faulting_member: Some(3), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(8), &allocator)?;
+
+    let report = cluster.driver().fetch_dpu_fault_context(&DpuId::new(0, 1, 3))?;
+
+    assert_eq!(FaultCause::Unknown, report.cause);
+    assert_eq!(NR_OF_THREADS as usize, report.threads.len());
+    assert!(!report.threads[0].is_running);
+    assert!(report.threads[1].is_running);
+    assert_eq!(0x42, report.threads[1].pc);
+    assert_eq!(vec![1, 2, 3, 4], report.threads[1].registers);
+    assert_eq!("unknown fault on DPU 0.1.3\n  thread  1: pc = 0x0042 z = 0 c = 0\n    r0  00000001 00000002 00000003 00000004\n",
+               report.to_string());
+
+    Ok(())
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn hardware_faults_prevail_over_breakpoints() -> Result<(), ClusterError> {
+    let dpu = DpuId::new(0, 1, 3);
+    let cases = vec![
+        ([Some(0), None, None], FaultCause::Breakpoint, 0),
+        ([Some(0), None, Some(1)], FaultCause::Memory, 1),
+        ([Some(0), Some(1), Some(0)], FaultCause::Dma, 1),
+    ];
+
+    for (raised_faults, cause, thread) in cases {
+        let allocator = FakeAllocator { faulting_member: Some(3), raised_faults, ..Default::default() };
+        let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(8), &allocator)?;
+
+        let report = cluster.driver().fetch_dpu_fault_context(&dpu)?;
+
+        assert_eq!((cause, Some(thread)), (report.cause, report.thread));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn runaway_dpus_are_stopped_on_timeout() -> Result<(), ClusterError> {
+    let calls = Arc::new(Mutex::new(Vec::default()));
+    let allocator = FakeAllocator { calls: calls.clone(), running_member: Some(2), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(16), &allocator)?;
+    let options = RunOptions {
+        poll_interval: Duration::from_millis(1),
+        backoff: Some(Duration::from_millis(4)),
+        timeout: Some(Duration::from_millis(20)),
+        ..Default::default()
+    };
+
+    match cluster.driver().run_with(&View::all(), options)? {
+        RunStatus::TimedOut(dpus) => assert_eq!(vec![DpuId::new(0, 1, 2), DpuId::new(1, 1, 2)], dpus),
+        status => panic!("the run should time out: {:?}", status),
+    }
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(2, calls.iter().filter(|call| **call == "fault_dpu").count());
+    assert!(!calls.contains(&"fault_all"));
+    assert!(calls.iter().filter(|call| **call == "poll_all").count() < 40);
+
+    Ok(())
+}
+
+#[test]
+fn runs_can_be_cancelled() -> Result<(), ClusterError> {
+    let allocator = FakeAllocator { running_member: Some(2), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(16), &allocator)?;
+    let dpu = DpuId::new(1, 1, 2);
+    let cancel_token = CancelToken::default();
+    cancel_token.cancel();
+
+    match cluster.driver().run_with(&View::one(dpu), RunOptions { cancel_token: Some(cancel_token), ..Default::default() }) {
+        Err(ClusterError::RunCancelled(dpus)) => assert_eq!(vec![dpu], dpus),
+        other => panic!("the run should be cancelled: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn broadcasts_use_one_transfer_per_rank() -> Result<(), ClusterError> {
+    let calls = Arc::new(Mutex::new(Vec::default()));
+    let allocator = FakeAllocator { calls: calls.clone(), ..Default::default() };
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(16), &allocator)?;
+    calls.lock().unwrap().clear();
+
+    cluster.driver().broadcast_to_memory(&View::all(), 0, &[1, 2, 3, 4, 5, 6, 7, 8])?;
+
+    assert_eq!(vec!["copy_to_mrams", "copy_to_mrams"], *calls.lock().unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn ranks_are_driven_in_parallel() -> Result<(), ClusterError> {
+    let allocator = FakeAllocator::default();
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(16).with_rank_workers(2), &allocator)?;
+    let transfer = || ToDpuTransfer::default()
+        .add(DpuId::new(0, 0, 0), 0, [1u8; 8].as_ref())
+        .add(DpuId::new(1, 0, 0), 0, [2u8; 8].as_ref());
+    allocator.threads.lock().unwrap().clear();
+
+    cluster.driver().copy_to_memory(&mut transfer())?;
+    cluster.driver().boot(&View::all())?;
+
+    let threads = allocator.threads.lock().unwrap().clone();
+    assert!(!threads.contains(&thread::current().id()));
+    assert!(threads.len() >= 2);
+
+    // the status is polled on the calling thread
+    allocator.threads.lock().unwrap().clear();
+    cluster.driver().fetch_status(&View::all())?;
+    assert_eq!(vec![thread::current().id()], allocator.threads.lock().unwrap().iter().copied().collect::<Vec<_>>());
+
+    let allocator = FakeAllocator::default();
+    let cluster = Cluster::create_with(ClusterConfiguration::for_functional_simulator(16), &allocator)?;
+    allocator.threads.lock().unwrap().clear();
+
+    cluster.driver().copy_to_memory(&mut transfer())?;
+    cluster.driver().run(&View::all())?;
+
+    assert_eq!(vec![thread::current().id()], allocator.threads.lock().unwrap().iter().copied().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn leases_can_be_packed_by_slice_or_by_rank() -> Result<(), ClusterError> {
+    let allocator = FakeAllocator::default();
+    let cluster = Arc::new(Cluster::create_with(ClusterConfiguration::for_functional_simulator(16), &allocator)?);
+
+    let first = cluster.lease(3, LeasePolicy::PackBySlice)?;
+    let second = cluster.lease(4, LeasePolicy::PackBySlice)?;
+    let third = cluster.lease(5, LeasePolicy::PackByRank)?;
+
+    let slices_of = |dpus: &[DpuId]| dpus.iter().map(|dpu| { let (rank, slice, _) = dpu.members(); (rank, slice) }).collect::<HashSet<_>>();
+    let ranks_of = |dpus: &[DpuId]| dpus.iter().map(|dpu| dpu.members().0).collect::<HashSet<_>>();
+
+    assert_eq!(1, slices_of(first.dpus()).len());
+    assert_eq!(1, slices_of(second.dpus()).len());
+    assert_ne!(slices_of(first.dpus()), slices_of(second.dpus()));
+    assert_eq!(1, ranks_of(third.dpus()).len());
+
+    let mut all = first.dpus().to_vec();
+    all.extend_from_slice(second.dpus());
+    all.extend_from_slice(third.dpus());
+    assert_eq!(12, all.iter().collect::<HashSet<_>>().len());
+
+    Ok(())
+}
+
+// the fake ranks read back zeros: any other written value looks corrupted
+#[test]
+fn corrupted_transfers_are_reported_and_counted() -> Result<(), ClusterError> {
+    let config = ClusterConfiguration::for_functional_simulator(8).verifying_transfers(Verification::Compare);
+    let cluster = Cluster::create_with(config, &FakeAllocator::default())?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 1, 2);
+
+    driver.copy_to_memory(&mut ToDpuTransfer::default().add(dpu, 8, [0u8; 8].as_ref()))?;
+
+    match driver.copy_to_memory(&mut ToDpuTransfer::default().add(dpu, 8, [0, 0, 0, 0, 1u8, 0, 0, 0].as_ref())) {
+        Err(ClusterError::CorruptedTransfer { dpu: corrupted, offset }) => assert_eq!((dpu, 12), (corrupted, offset)),
+        other => panic!("the transfer should be corrupted: {:?}", other),
+    }
+
+    driver.set_verification(Verification::Checksum);
+    match driver.copy_to_memory(&mut ToDpuTransfer::default().add(dpu, 8, [0, 0, 0, 0, 1u8, 0, 0, 0].as_ref())) {
+        Err(ClusterError::CorruptedTransfer { offset, .. }) => assert_eq!(8, offset),
+        other => panic!("the transfer should be corrupted: {:?}", other),
+    }
+
+    driver.set_verification(Verification::Disabled);
+    driver.copy_to_memory(&mut ToDpuTransfer::default().add(dpu, 8, [0, 0, 0, 0, 1u8, 0, 0, 0].as_ref()))?;
+
+    assert_eq!(vec![(dpu, 2)], driver.corrupted_transfers().into_iter().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn corrupted_programs_are_not_resident() -> Result<(), ClusterError> {
+    let config = ClusterConfiguration::for_functional_simulator(8).verifying_transfers(Verification::Compare);
+    let cluster = Cluster::create_with(config, &FakeAllocator::default())?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 1);
+
+    // the bits above the 48 bits of an instruction are not compared
+    let program = Program::new(vec![0xFFFF_0000_0000_0000; 4], vec![0; 4], None);
+    assert!(driver.load_if_needed(&dpu, &program)?);
+
+    let program = Program::new(vec![0; 4], vec![0, 0, 3, 0], None);
+    match driver.load(&View::all(), &program) {
+        Err(ClusterError::CorruptedTransfer { dpu: corrupted, offset }) => assert_eq!((DpuId::new(0, 0, 0), 2), (corrupted, offset)),
+        other => panic!("the load should be corrupted: {:?}", other),
+    }
+
+    assert_eq!(None, driver.resident_program(&dpu));
+    assert_eq!(NR_OF_SLICES as usize * NR_OF_MEMBERS as usize, driver.corrupted_transfers().len());
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use dpu_cluster_core::catalog::DataCatalog;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::error::ClusterError;
+
+#[test]
+fn fragments_are_located_by_dataset() -> Result<(), ClusterError> {
+    let catalog = DataCatalog::default();
+    let (first, second) = (DpuId::new(0, 0, 0), DpuId::new(0, 1, 0));
+
+    let recorded = catalog.record("words", "a".to_string(), first, 0, 64)?;
+    catalog.record("words", "b".to_string(), second, 0, 64)?;
+    catalog.record("ids", 42u32, first, 64, 8)?;
+
+    assert_eq!(Some(recorded), catalog.locate("words", &"a".to_string()));
+    assert_eq!(Some(second), catalog.locate("words", &"b".to_string()).map(|location| location.dpu));
+    assert_eq!(None, catalog.locate("words", &"c".to_string()));
+    assert_eq!(None, catalog.locate("ids", &"a".to_string()));
+    assert_eq!(vec![(42u32, catalog.locate("ids", &42u32).unwrap())], catalog.fragments_on("ids", &first));
+    assert!(catalog.holds_fragments_on("words", &second));
+    assert!(!catalog.holds_fragments_on("ids", &second));
+
+    match catalog.record("ids", "a".to_string(), second, 64, 8) {
+        Err(ClusterError::DatasetTypeMismatch(dataset)) => assert_eq!("ids", dataset),
+        other => panic!("unexpected record: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn overwritten_fragments_are_forgotten() -> Result<(), ClusterError> {
+    let catalog = DataCatalog::default();
+    let dpu = DpuId::new(0, 0, 0);
+
+    let old = catalog.record("words", 0u32, dpu, 0, 64)?;
+    catalog.record("words", 1u32, dpu, 64, 64)?;
+    let new = catalog.record("ids", 0u32, dpu, 32, 8)?;
+
+    assert!(new.version > old.version);
+    assert_eq!(None, catalog.locate("words", &0u32));
+    assert!(catalog.locate("words", &1u32).is_some());
+
+    let moved = catalog.record("ids", 0u32, DpuId::new(0, 0, 1), 0, 8)?;
+    assert_eq!(Some(moved), catalog.locate("ids", &0u32));
+
+    catalog.invalidate(&[dpu]);
+    assert_eq!(vec!["ids".to_string()], catalog.datasets());
+
+    catalog.remove_dataset("ids");
+    assert!(catalog.datasets().is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,673 @@
+#![cfg(feature = "mock")]
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::view::View;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::lease::LeasePolicy;
+use dpu_cluster_core::memory::FromDpuTransfer;
+use dpu_cluster_core::memory::ToDpuTransfer;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::pipeline::plan::Plan;
+use dpu_cluster_core::pipeline::PipelineError;
+use dpu_cluster_core::pipeline::GroupPolicy;
+use dpu_cluster_core::pipeline::transfer::MemoryTransfers;
+use dpu_cluster_core::pipeline::transfer::InputMemoryTransfer;
+use dpu_cluster_core::pipeline::transfer::OutputMemoryTransfer;
+
+// the mock has a limited number of hardware ranks, shared by the tests of this file
+static HARDWARE: Mutex<()> = Mutex::new(());
+
+fn lock_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("dpu-cluster-locks-{}-{}", name, process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+fn can_run_a_program_on_one_dpu() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let view = View::one(DpuId::new(0, 0, 0));
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+
+    driver.load(&view, &program)?;
+
+    match driver.run(&view)? {
+        RunStatus::Idle => Ok(()),
+        _ => panic!("the dpu should be idle after its execution"),
+    }
+}
+
+#[test]
+fn can_copy_to_and_from_mram() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+    let input = vec![1u8, 2, 3, 4, 5, 6, 7, 8];
+    let mut output = vec![0u8; 8];
+
+    driver.copy_to_memory(&mut ToDpuTransfer::default().add(dpu, 64, input.as_slice()))?;
+    driver.copy_from_memory(&mut FromDpuTransfer::default().add(dpu, 64, output.as_mut_slice()))?;
+
+    assert_eq!(input, output);
+
+    Ok(())
+}
+
+#[test]
+fn can_transfer_several_regions_of_one_dpu() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(2))?;
+    let driver = cluster.driver();
+    let (first, second) = (DpuId::new(0, 0, 0), DpuId::new(1, 0, 0));
+    let (header, body, other) = (vec![1u8; 8], vec![2u8; 16], vec![3u8; 8]);
+
+    driver.copy_to_memory(&mut ToDpuTransfer::default()
+        .add(first, 0, header.as_slice())
+        .add(first, 64, body.as_slice())
+        .add(second, 0, other.as_slice()))?;
+
+    let (mut header_output, mut body_output, mut other_output) = (vec![0u8; 8], vec![0u8; 16], vec![0u8; 8]);
+    driver.copy_from_memory(&mut FromDpuTransfer::default()
+        .add(first, 0, header_output.as_mut_slice())
+        .add(first, 64, body_output.as_mut_slice())
+        .add(second, 0, other_output.as_mut_slice()))?;
+
+    assert_eq!(header, header_output);
+    assert_eq!(body, body_output);
+    assert_eq!(other, other_output);
+
+    Ok(())
+}
+
+#[test]
+fn unaligned_regions_are_bounced_or_rejected() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(2))?;
+    let driver = cluster.driver();
+    let (first, second) = (DpuId::new(0, 0, 0), DpuId::new(1, 0, 0));
+    let background = vec![0xFFu8; 32];
+
+    driver.copy_to_memory(&mut ToDpuTransfer::default().add(first, 0, background.as_slice()).add(second, 0, background.as_slice()))?;
+
+    match driver.copy_to_memory(&mut ToDpuTransfer::default().add(first, 0, background.as_slice()).add(first, 3, &[1u8, 2, 3][..])) {
+        Err(ClusterError::UnalignedMramAccess { offset: 3, length: 3, .. }) => (),
+        _ => panic!("the region is not aligned on 8 bytes"),
+    }
+
+    driver.copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions()
+        .add(first, 3, &[1u8, 2, 3][..])
+        .add(first, 13, &[4u8; 6][..])
+        .add(second, 5, &[7u8; 2][..])
+        .add(second, 16, &[1u8, 2, 3][..])
+        .add(second, 19, &[4u8, 5, 6][..]))?;
+
+    let (mut first_output, mut second_output, mut middle) = (vec![0u8; 32], vec![0u8; 32], vec![0u8; 5]);
+    driver.copy_from_memory(&mut FromDpuTransfer::default()
+        .add(first, 0, first_output.as_mut_slice())
+        .add(second, 0, second_output.as_mut_slice()))?;
+    driver.copy_from_memory(&mut FromDpuTransfer::default().bouncing_unaligned_regions().add(first, 12, middle.as_mut_slice()))?;
+
+    let mut expected = background.clone();
+    expected[3..6].copy_from_slice(&[1, 2, 3]);
+    expected[13..19].copy_from_slice(&[4; 6]);
+    assert_eq!(expected, first_output);
+    assert_eq!(&expected[12..17], middle.as_slice());
+
+    let mut expected = background.clone();
+    expected[5..7].copy_from_slice(&[7; 2]);
+    expected[16..22].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+    assert_eq!(expected, second_output);
+
+    Ok(())
+}
+
+#[test]
+fn out_of_bounds_regions_are_rejected() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let dpu = DpuId::new(0, 0, 0);
+    let limit = cluster.driver().rank_description.memories.mram_size;
+    let mut data = vec![0u8; 16];
+
+    match cluster.driver().copy_to_memory(&mut ToDpuTransfer::default().add(dpu, limit - 8, data.as_slice())) {
+        Err(ClusterError::OutOfBounds { offset, length: 16, .. }) => assert_eq!(limit - 8, offset),
+        _ => panic!("the region ends after the MRAM"),
+    }
+    match cluster.driver().copy_from_memory(&mut FromDpuTransfer::default().add(dpu, limit, data.as_mut_slice())) {
+        Err(ClusterError::OutOfBounds { limit: found, .. }) => assert_eq!(limit, found),
+        _ => panic!("the region starts after the MRAM"),
+    }
+
+    let outputs = Plan::from(0..4u8)
+        .for_simple_model(move |idx: u8| MemoryTransfers {
+            inputs: vec![InputMemoryTransfer::from_u8_vec(if idx == 2 { limit } else { 0 }, vec![idx; 8])],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    let mut results = Vec::default();
+    for output in outputs {
+        match output {
+            Ok((idx, _)) => results.push(idx),
+            Err(PipelineError::InfrastructureError(ClusterError::OutOfBounds { offset, length: 8, .. })) => assert_eq!(limit, offset),
+            Err(err) => panic!("unexpected error: {:?}", err),
+        }
+    }
+    results.sort();
+
+    assert_eq!(vec![0, 1, 3], results);
+
+    Ok(())
+}
+
+#[test]
+fn can_broadcast_one_buffer_to_many_dpus() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(3))?;
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let query = vec![5u8; 8];
+
+    cluster.driver().broadcast_to_memory(&View::all(), 128, &query)?;
+
+    for rank in 0..3 {
+        let mut output = vec![0u8; 8];
+        cluster.driver().copy_from_memory(&mut FromDpuTransfer::default().add(DpuId::new(rank, 0, 0), 128, output.as_mut_slice()))?;
+        assert_eq!(query, output);
+    }
+
+    cluster.driver().broadcast_to_memory(&View::all(), 131, &[1, 2, 3])?;
+
+    for rank in 0..3 {
+        let mut output = vec![0u8; 8];
+        cluster.driver().copy_from_memory(&mut FromDpuTransfer::default().add(DpuId::new(rank, 0, 0), 128, output.as_mut_slice()))?;
+        assert_eq!(vec![5, 5, 5, 1, 2, 3, 5, 5], output);
+    }
+
+    let outputs = Plan::from(0..6u8)
+        .for_simple_model(|idx: u8| MemoryTransfers {
+            inputs: vec![
+                InputMemoryTransfer::from_u8_vec(0, vec![idx; 8]),
+                InputMemoryTransfer::broadcast(64, vec![9u8; 8]),
+                InputMemoryTransfer::broadcast(66, vec![8u8; 3])
+            ],
+            output: OutputMemoryTransfer { offset: 64, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    for output in outputs {
+        let (_, content) = output?;
+        assert_eq!(vec![9, 9, 8, 8, 8, 9, 9, 9], content);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn inputs_overlapping_a_broadcast_are_written_in_order() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(3))?;
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let shared: Arc<[u8]> = Arc::from(vec![9u8; 8]);
+
+    let outputs = Plan::from(0..6u8)
+        .for_simple_model(move |idx: u8| MemoryTransfers {
+            inputs: vec![
+                InputMemoryTransfer::from_u8_vec(0, vec![idx; 2]),
+                InputMemoryTransfer::broadcast(0, shared.clone()),
+                InputMemoryTransfer::from_u8_vec(2, vec![idx; 2])
+            ],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    for output in outputs {
+        let (idx, content) = output?;
+        assert_eq!(vec![9, 9, idx, idx, 9, 9, 9, 9], content);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn shared_buffers_are_streamed_without_copies() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(2))?;
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let shared: Arc<[u8]> = Arc::from(vec![7u8; 8]);
+
+    for dpu in &[DpuId::new(0, 0, 0), DpuId::new(1, 0, 0)] {
+        cluster.driver().copy_to_memory(&mut ToDpuTransfer::default().add(*dpu, 64, &shared[..]))?;
+    }
+
+    let job_buffer = shared.clone();
+    let outputs = Plan::from(0..4u8)
+        .for_simple_model(move |idx: u8| MemoryTransfers {
+            inputs: vec![InputMemoryTransfer::from_buffer(0, job_buffer.clone())],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    for output in outputs {
+        let (_, content) = output?;
+        assert_eq!(&shared[..], content.as_slice());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_drive_a_simple_pipeline() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let inputs = (0..4u8).map(|idx| (idx, vec![idx; 8]));
+
+    let outputs = Plan::from(inputs)
+        .for_simple_model(|(idx, content): (u8, Vec<u8>)| MemoryTransfers {
+            inputs: vec![InputMemoryTransfer::from_u8_vec(0, content)],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    let mut results = Vec::default();
+    for output in outputs {
+        results.push(output?);
+    }
+    results.sort();
+
+    assert_eq!((0..4u8).map(|idx| (idx, vec![idx; 8])).collect::<Vec<_>>(), results);
+
+    Ok(())
+}
+
+#[test]
+fn can_create_a_cluster_on_hardware() -> Result<(), ClusterError> {
+    let _hardware = HARDWARE.lock().unwrap();
+    let directory = lock_directory("hardware");
+    let cluster = Cluster::create(ClusterConfiguration::for_hardware(100).lock_directory(&directory))?;
+
+    assert_eq!((2, 8, 8), cluster.topology());
+
+    drop(cluster);
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn can_debug_a_running_dpu() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+    let view = View::one(dpu);
+
+    driver.load(&view, &Program::new(vec![0; 16], vec![0; 16], None))?;
+    driver.boot(&view)?;
+
+    let mut debugger = cluster.debug(dpu)?;
+    assert_eq!(vec![0], debugger.running_threads());
+
+    debugger.step(0)?;
+    debugger.step(0)?;
+    assert_eq!(Some(2), debugger.pc(0));
+
+    debugger.registers_mut(0).unwrap()[3] = 0xCAFE;
+    debugger.step(0)?;
+    assert_eq!(0xCAFE, debugger.registers(0).unwrap()[3]);
+    assert_eq!(None, debugger.pc(24));
+    assert!(debugger.registers(24).is_none());
+    assert!(debugger.set_pc(24, 0).is_err());
+
+    let mut wram = [0u32; 2];
+    debugger.write_wram(4, &[1, 2])?;
+    debugger.read_wram(4, &mut wram)?;
+    assert_eq!([1, 2], wram);
+
+    let mut mram = [0u8; 4];
+    debugger.write_mram(64, &[1, 2, 3, 4])?;
+    debugger.read_mram(64, &mut mram)?;
+    assert_eq!([1, 2, 3, 4], mram);
+
+    debugger.resume()?;
+
+    match driver.fetch_status(&view)? {
+        RunStatus::Running => (),
+        _ => panic!("the dpu should be running after being resumed"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dropped_debuggers_leave_the_fault_process() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+    let view = View::one(dpu);
+
+    driver.load(&view, &Program::new(vec![0; 16], vec![0; 16], None))?;
+    driver.boot(&view)?;
+
+    drop(cluster.debug(dpu)?);
+
+    match driver.fetch_status(&view)? {
+        RunStatus::Fault(_) => panic!("the dpu should not stay in fault"),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn leased_dpus_are_returned_to_the_pool_on_drop() -> Result<(), ClusterError> {
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_functional_simulator(4))?);
+
+    let lease = cluster.lease(3, LeasePolicy::default())?;
+    assert_eq!(3, lease.dpus().len());
+    assert!(lease.dpus().iter().all(|dpu| cluster.owner_of(dpu) == Some(lease.owner())));
+
+    match cluster.lease(2, LeasePolicy::default()) {
+        Err(ClusterError::NotEnoughResources { expected: 2, found: 1 }) => (),
+        _ => panic!("only one dpu should be left"),
+    }
+
+    drop(lease);
+
+    assert_eq!(4, cluster.lease(4, LeasePolicy::default())?.dpus().len());
+
+    Ok(())
+}
+
+#[test]
+fn can_drive_independent_pipelines_on_leases() -> Result<(), PipelineError> {
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_functional_simulator(2))?);
+    let leases = vec![cluster.lease(1, LeasePolicy::default())?, cluster.lease(1, LeasePolicy::default())?];
+
+    let workloads = leases.into_iter()
+        .map(|lease| thread::spawn(move || -> Result<Vec<(u8, Vec<u8>)>, PipelineError> {
+            let program = Program::new(vec![0; 16], vec![0; 16], None);
+            let inputs = (0..4u8).map(|idx| (idx, vec![idx; 8]));
+
+            let outputs = Plan::from(inputs)
+                .for_simple_model(|(idx, content): (u8, Vec<u8>)| MemoryTransfers {
+                    inputs: vec![InputMemoryTransfer::from_u8_vec(0, content)],
+                    output: OutputMemoryTransfer { offset: 0, length: 8 },
+                    program: None,
+                    key: idx
+                })
+                .leasing(lease)
+                .running(&program)
+                .build()?;
+
+            outputs.collect()
+        }))
+        .collect::<Vec<_>>();
+
+    for workload in workloads {
+        let mut results = workload.join().unwrap()?;
+        results.sort();
+
+        assert_eq!((0..4u8).map(|idx| (idx, vec![idx; 8])).collect::<Vec<_>>(), results);
+    }
+
+    assert!(cluster.owner_of(&DpuId::new(0, 0, 0)).is_none());
+    assert!(cluster.owner_of(&DpuId::new(1, 0, 0)).is_none());
+
+    Ok(())
+}
+
+fn program_marking_mram_with(marker: u8) -> Program {
+    let mut mram_sections = HashMap::default();
+    mram_sections.insert(64, vec![marker; 8]);
+
+    Program::from_sections(vec![(0, vec![0; 16])].into_iter().collect(), vec![(0, vec![0; 16])].into_iter().collect(), mram_sections)
+}
+
+#[test]
+fn programs_are_only_reloaded_when_needed() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+    let (first, second) = (program_marking_mram_with(1), program_marking_mram_with(2));
+
+    assert_eq!(None, driver.resident_program(&dpu));
+    assert!(driver.load_if_needed(&dpu, &first)?);
+    assert!(!driver.load_if_needed(&dpu, &first)?);
+    assert!(driver.load_if_needed(&dpu, &second)?);
+    assert_eq!(Some(second.id()), driver.resident_program(&dpu));
+
+    driver.load(&View::all(), &first)?;
+    assert_eq!(Some(first.id()), driver.resident_program(&dpu));
+
+    Ok(())
+}
+
+#[test]
+fn programs_patched_by_a_debugger_are_not_resident() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+    let program = program_marking_mram_with(1);
+
+    for patch_iram in &[true, false] {
+        driver.load_if_needed(&dpu, &program)?;
+        let debugger = cluster.debug(dpu)?;
+        if *patch_iram {
+            debugger.write_iram(0, &[0])?;
+        } else {
+            debugger.write_wram(0, &[0])?;
+        }
+        drop(debugger);
+
+        assert_eq!(None, driver.resident_program(&dpu));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pipeline_jobs_can_run_different_programs() -> Result<(), PipelineError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(2))?;
+    let programs = [Arc::new(program_marking_mram_with(0xA)), Arc::new(program_marking_mram_with(0xB))];
+
+    let outputs = Plan::from(0..8u8)
+        .for_simple_model(move |idx: u8| MemoryTransfers {
+            inputs: vec![InputMemoryTransfer::from_u8_vec(0, vec![idx; 8])],
+            output: OutputMemoryTransfer { offset: 64, length: 8 },
+            program: Some(programs[(idx % 2) as usize].clone()),
+            key: idx
+        })
+        .driving(cluster)
+        .grouped_by(GroupPolicy::Dpu)
+        .build()?;
+
+    for output in outputs {
+        let (idx, content) = output?;
+        let marker = if idx % 2 == 0 { 0xA } else { 0xB };
+        assert_eq!(vec![marker; 8], content);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn cataloged_fragments_are_reused_by_the_next_plans() -> Result<(), PipelineError> {
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_functional_simulator(2))?);
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let job = |fragment: u32| (fragment, MemoryTransfers {
+        inputs: vec![InputMemoryTransfer::from_u8_vec(8, vec![0xFF; 8])],
+        output: OutputMemoryTransfer { offset: 0, length: 8 },
+        program: None,
+        key: fragment
+    });
+
+    let fragments = (0..2u32).map(|fragment| (fragment, InputMemoryTransfer::from_u8_vec(0, vec![fragment as u8 + 1; 8])));
+    let outputs = Plan::from(0..2u32)
+        .for_persistent_model(job, fragments)
+        .cataloged_as("fragments")
+        .leasing(cluster.lease(2, LeasePolicy::default())?)
+        .running(&program)
+        .build()?;
+    assert_eq!(2, outputs.count());
+
+    let first = cluster.catalog().locate("fragments", &0u32).expect("the fragment should be cataloged");
+    let second = cluster.catalog().locate("fragments", &1u32).expect("the fragment should be cataloged");
+    assert_ne!(first.dpu, second.dpu);
+    assert_eq!(vec![(1u32, second)], cluster.catalog().fragments_on("fragments", &second.dpu));
+
+    let outputs = Plan::from(vec![1u32, 0, 1])
+        .for_persistent_model(job, Vec::default())
+        .cataloged_as("fragments")
+        .leasing(cluster.lease(2, LeasePolicy::default())?)
+        .build()?;
+
+    let mut results = outputs.collect::<Result<Vec<_>, _>>()?;
+    results.sort();
+    assert_eq!(vec![(0, vec![1; 8]), (1, vec![2; 8]), (1, vec![2; 8])], results);
+
+    // the fragments overwritten through the driver are forgotten
+    cluster.driver().copy_to_memory(&mut ToDpuTransfer::default().add(first.dpu, 0, &[0u8; 8][..]))?;
+    assert_eq!(None, cluster.catalog().locate("fragments", &0u32));
+    assert_eq!(Some(second), cluster.catalog().locate("fragments", &1u32));
+
+    let mram_sections = vec![(0, vec![0u8; 8])].into_iter().collect();
+    let overwriting = Program::from_sections(vec![(0, vec![0; 16])].into_iter().collect(), vec![(0, vec![0; 16])].into_iter().collect(), mram_sections);
+    cluster.driver().load(&View::one(second.dpu), &overwriting)?;
+    assert!(cluster.catalog().datasets().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn new_fragments_join_the_cataloged_ones() -> Result<(), PipelineError> {
+    let _hardware = HARDWARE.lock().unwrap();
+    let directory = lock_directory("fragments");
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_hardware(64).lock_directory(&directory))?);
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let job = |fragment: u32| (fragment, MemoryTransfers {
+        inputs: vec![InputMemoryTransfer::from_u8_vec(8, vec![0xFF; 8])],
+        output: OutputMemoryTransfer { offset: 0, length: 8 },
+        program: None,
+        key: fragment
+    });
+    let fragments = |fragments: Range<u32>| fragments.map(|fragment| (fragment, InputMemoryTransfer::from_u8_vec(0, vec![fragment as u8 + 1; 8])));
+
+    let outputs = Plan::from(0..2u32)
+        .for_persistent_model(job, fragments(0..2))
+        .cataloged_as("fragments")
+        .leasing(cluster.lease(64, LeasePolicy::default())?)
+        .grouped_by(GroupPolicy::Slice)
+        .running(&program)
+        .build()?;
+    assert_eq!(2, outputs.count());
+
+    let outputs = Plan::from(0..3u32)
+        .for_persistent_model(job, fragments(2..3))
+        .cataloged_as("fragments")
+        .leasing(cluster.lease(64, LeasePolicy::default())?)
+        .grouped_by(GroupPolicy::Slice)
+        .build()?;
+
+    let mut results = outputs.collect::<Result<Vec<_>, _>>()?;
+    results.sort();
+    assert_eq!(vec![(0, vec![1; 8]), (1, vec![2; 8]), (2, vec![3; 8])], results);
+
+    drop(cluster);
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn anonymous_datasets_are_forgotten_with_their_plan() -> Result<(), PipelineError> {
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_functional_simulator(1))?);
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+
+    let outputs = Plan::from(0..2u32)
+        .for_persistent_model(|idx: u32| (0u32, MemoryTransfers {
+            inputs: Vec::default(),
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        }), vec![(0u32, InputMemoryTransfer::from_u8_vec(0, vec![7; 8]))])
+        .leasing(cluster.lease(1, LeasePolicy::default())?)
+        .running(&program)
+        .build()?;
+
+    for output in outputs {
+        assert_eq!(vec![7; 8], output?.1);
+    }
+    assert!(cluster.catalog().datasets().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn the_regions_of_a_cluster_are_shared() -> Result<(), ClusterError> {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let dpu = DpuId::new(0, 0, 0);
+
+    let region = cluster.mram_allocator().allocate::<u64>(dpu, "shared", 4)?;
+
+    assert_eq!(Some(region.offset()), cluster.mram_allocator().region::<u64>(&dpu, "shared").map(|region| region.offset()));
+    match cluster.mram_allocator().allocate::<u64>(dpu, "shared", 4) {
+        Err(ClusterError::RegionAlreadyAllocated { .. }) => (),
+        _ => panic!("the region is already allocated in the cluster"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn regions_freed_before_the_transfer_are_rejected() -> Result<(), PipelineError> {
+    let cluster = Arc::new(Cluster::create(ClusterConfiguration::for_functional_simulator(1))?);
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let dpu = DpuId::new(0, 0, 0);
+
+    let region = cluster.mram_allocator().allocate::<u32>(dpu, "freed", 2)?;
+    let input = InputMemoryTransfer::to_region(&cluster.mram_allocator(), &region, vec![1u32, 2])?;
+    cluster.mram_allocator().free(&dpu, "freed")?;
+
+    let outputs = Plan::from(vec![input])
+        .for_simple_model(|input| MemoryTransfers {
+            inputs: vec![input],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: ()
+        })
+        .leasing(cluster.lease(1, LeasePolicy::default())?)
+        .running(&program)
+        .build()?;
+
+    match outputs.collect::<Vec<_>>().as_slice() {
+        [Err(PipelineError::InfrastructureError(ClusterError::UnknownRegion { region, .. }))] => assert_eq!("freed", region),
+        other => panic!("unexpected results: {:?}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "mock")]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::lock;
+use dpu_sys::DpuType;
+
+const NR_OF_DPUS_PER_RANK: u32 = 64;
+const NR_OF_HARDWARE_RANKS: u32 = 4;
+
+// the mock has a limited number of hardware ranks, shared by the tests of this file
+static HARDWARE: Mutex<()> = Mutex::new(());
+
+fn lock_directory(name: &str) -> PathBuf {
+    let directory = env::temp_dir().join(format!("dpu-locks-{}-{}", name, process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+#[test]
+fn free_ranks_are_allocated_next_to_busy_ones() -> Result<(), ClusterError> {
+    let _hardware = HARDWARE.lock().unwrap();
+    let directory = lock_directory("busy");
+
+    let first = Cluster::create(ClusterConfiguration::for_hardware(2 * NR_OF_DPUS_PER_RANK).lock_directory(&directory))?;
+    let second = Cluster::create(ClusterConfiguration::for_hardware(2 * NR_OF_DPUS_PER_RANK).lock_directory(&directory))?;
+    assert_eq!((2, 8, 8), second.topology());
+
+    match Cluster::create(ClusterConfiguration::for_hardware(NR_OF_DPUS_PER_RANK).lock_directory(&directory)) {
+        Err(ClusterError::NotEnoughResources { expected, found: 0 }) => assert_eq!(NR_OF_DPUS_PER_RANK, expected),
+        other => panic!("unexpected result: {:?}", other.map(|cluster| cluster.topology())),
+    }
+
+    drop(first);
+    let third = Cluster::create(ClusterConfiguration::for_hardware(2 * NR_OF_DPUS_PER_RANK).lock_directory(&directory))?;
+    assert_eq!((2, 8, 8), third.topology());
+
+    drop((second, third));
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn can_wait_for_busy_ranks() -> Result<(), ClusterError> {
+    let _hardware = HARDWARE.lock().unwrap();
+    let directory = lock_directory("wait");
+
+    let first = Cluster::create(ClusterConfiguration::for_hardware(NR_OF_HARDWARE_RANKS * NR_OF_DPUS_PER_RANK).lock_directory(&directory))?;
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        drop(first);
+    });
+
+    let config = ClusterConfiguration::for_hardware(NR_OF_DPUS_PER_RANK)
+        .lock_directory(&directory)
+        .wait_for_ranks(Duration::from_secs(10));
+    let second = Cluster::create(config)?;
+    assert_eq!((1, 8, 8), second.topology());
+
+    releaser.join().unwrap();
+
+    drop(second);
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn waiting_for_busy_ranks_times_out() -> Result<(), ClusterError> {
+    let _hardware = HARDWARE.lock().unwrap();
+    let directory = lock_directory("timeout");
+
+    let first = Cluster::create(ClusterConfiguration::for_hardware(NR_OF_HARDWARE_RANKS * NR_OF_DPUS_PER_RANK).lock_directory(&directory))?;
+
+    let config = ClusterConfiguration::for_hardware(NR_OF_DPUS_PER_RANK)
+        .lock_directory(&directory)
+        .wait_for_ranks(Duration::from_millis(50));
+    match Cluster::create(config) {
+        Err(ClusterError::NotEnoughResources { .. }) => (),
+        other => panic!("unexpected result: {:?}", other.map(|cluster| cluster.topology())),
+    }
+
+    drop(first);
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn rank_slots_are_bounded_by_the_number_of_ranks() -> Result<(), ClusterError> {
+    let directory = lock_directory("slots");
+    let nr_of_slots = NR_OF_HARDWARE_RANKS as usize;
+
+    let slots = (0..nr_of_slots)
+        .map(|_| lock::lock_rank_slot(&directory, DpuType::Hardware, nr_of_slots))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match lock::lock_rank_slot(&directory, DpuType::Hardware, nr_of_slots) {
+        Err(ClusterError::RankBusy) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    drop(slots);
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn simulated_ranks_are_not_locked() -> Result<(), ClusterError> {
+    let directory = lock_directory("simulator");
+
+    let _first = Cluster::create(ClusterConfiguration::for_functional_simulator(1).lock_directory(&directory))?;
+    let _second = Cluster::create(ClusterConfiguration::for_functional_simulator(1).lock_directory(&directory))?;
+
+    assert_eq!(0, fs::read_dir(&directory).unwrap().count());
+
+    fs::remove_dir_all(&directory).unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use dpu_cluster_core::pod;
+use dpu_cluster_core::pod::DpuPod;
+
+#[derive(Debug, Clone, Copy, PartialEq, DpuPod)]
+#[repr(C)]
+struct Record {
+    key: u32,
+    length: u16,
+    flags: [u8; 2],
+    value: u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, DpuPod)]
+#[repr(transparent)]
+struct Handle(u32);
+
+fn records() -> Vec<Record> {
+    (0..4).map(|idx| Record { key: idx, length: 2 * idx as u16, flags: [1, idx as u8], value: u64::MAX - idx as u64 }).collect()
+}
+
+#[test]
+fn derived_structs_are_viewed_as_bytes() {
+    let records = records();
+    let bytes = pod::as_bytes(&records);
+
+    assert_eq!(16 * records.len(), bytes.len());
+    assert_eq!(&[1, 0, 0, 0, 2, 0, 1, 1], &bytes[16..24]);
+    assert_eq!(records, pod::from_bytes::<Record>(bytes));
+    assert_eq!(vec![Handle(0x0403_0201)], pod::from_bytes::<Handle>(&[1, 2, 3, 4, 5]));
+}
+
+#[test]
+fn padded_structs_cannot_derive_dpu_pod() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn derived_structs_can_be_transferred() -> Result<(), dpu_cluster_core::pipeline::PipelineError> {
+    use dpu_cluster_core::cluster::Cluster;
+    use dpu_cluster_core::config::ClusterConfiguration;
+    use dpu_cluster_core::dpu::DpuId;
+    use dpu_cluster_core::memory::FromDpuTransfer;
+    use dpu_cluster_core::memory::ToDpuTransfer;
+    use dpu_cluster_core::pipeline::plan::Plan;
+    use dpu_cluster_core::pipeline::transfer::InputMemoryTransfer;
+    use dpu_cluster_core::pipeline::transfer::MemoryTransfers;
+    use dpu_cluster_core::pipeline::transfer::OutputMemoryTransfer;
+    use dpu_cluster_core::program::Program;
+
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let dpu = DpuId::new(0, 0, 0);
+    let records = records();
+    let mut output = vec![Record { key: 0, length: 0, flags: [0; 2], value: 0 }; records.len()];
+    let mut handle = Handle(0);
+
+    cluster.driver().copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions()
+        .add(dpu, 0, records.as_slice())
+        .add(dpu, 128, &Handle(42)))?;
+    cluster.driver().copy_from_memory(&mut FromDpuTransfer::default().bouncing_unaligned_regions()
+        .add(dpu, 0, output.as_mut_slice())
+        .add(dpu, 128, &mut handle))?;
+
+    assert_eq!(records, output);
+    assert_eq!(Handle(42), handle);
+
+    let program = Program::new(vec![0; 16], vec![0; 16], None);
+    let outputs = Plan::from(0..2u32)
+        .for_simple_model(|idx: u32| MemoryTransfers {
+            inputs: vec![InputMemoryTransfer::from_pod_vec(0, vec![Handle(idx); 2])],
+            output: OutputMemoryTransfer { offset: 0, length: 8 },
+            program: None,
+            key: idx
+        })
+        .driving(cluster)
+        .running(&program)
+        .build()?;
+
+    for output in outputs {
+        let (idx, content) = output?;
+        assert_eq!(vec![Handle(idx); 2], pod::from_bytes::<Handle>(&content));
+    }
+
+    Ok(())
+}
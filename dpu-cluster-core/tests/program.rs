@@ -0,0 +1,266 @@
+use std::env;
+use std::fs;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use dpu_sys::DpuRankDescription;
+use dpu_cluster_core::program::Memory;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::program::ProgramError;
+use dpu_cluster_core::program::Symbol;
+use dpu_cluster_core::program::SymbolAddress;
+use dpu_cluster_core::error::ClusterError;
+
+const SECTION_TYPE_PROGBITS: u32 = 1;
+const SECTION_TYPE_SYMTAB: u32 = 2;
+const SECTION_TYPE_STRTAB: u32 = 3;
+const SECTION_TYPE_NOBITS: u32 = 8;
+const SECTION_FLAG_ALLOC: u32 = 2;
+const ELF_MACHINE_DPU: u16 = 0xf5;
+
+struct Section {
+    kind: u32,
+    flags: u32,
+    address: u32,
+    content: Vec<u8>,
+    link: u32
+}
+
+fn section(kind: u32, flags: u32, address: u32, content: Vec<u8>) -> Section {
+    Section { kind, flags, address, content, link: 0 }
+}
+
+// a minimal DPU binary, with the same layout as the one produced by the DPU linker
+fn build_elf(symbols: &[(&str, u32, u32)]) -> Vec<u8> {
+    let mut names = vec![0u8];
+    let mut entries = vec![0u8; 16];
+    for (name, address, size) in symbols {
+        entries.extend_from_slice(&(names.len() as u32).to_le_bytes());
+        entries.extend_from_slice(&address.to_le_bytes());
+        entries.extend_from_slice(&size.to_le_bytes());
+        entries.extend_from_slice(&[0x11, 0, 1, 0]);
+        names.extend_from_slice(name.as_bytes());
+        names.push(0);
+    }
+
+    let iram = (1..=2u64).flat_map(|instruction| instruction.to_le_bytes().to_vec()).collect();
+    let sections = vec![
+        section(0, 0, 0, Vec::default()),
+        section(SECTION_TYPE_PROGBITS, SECTION_FLAG_ALLOC, 0x8000_0010, iram),
+        section(SECTION_TYPE_PROGBITS, SECTION_FLAG_ALLOC, 0x0000_0008, vec![1, 0, 0, 0, 2, 0, 0, 0]),
+        section(SECTION_TYPE_PROGBITS, SECTION_FLAG_ALLOC, 0x0800_0040, vec![0xAA; 8]),
+        section(SECTION_TYPE_NOBITS, SECTION_FLAG_ALLOC, 0x0000_0100, Vec::default()),
+        section(SECTION_TYPE_PROGBITS, 0, 0, vec![0xFF; 4]),
+        Section { kind: SECTION_TYPE_SYMTAB, flags: 0, address: 0, content: entries, link: 7 },
+        section(SECTION_TYPE_STRTAB, 0, 0, names),
+    ];
+
+    let mut elf = vec![0u8; 52];
+    elf[0..4].copy_from_slice(b"\x7fELF");
+    elf[4] = 1;
+    elf[5] = 1;
+    elf[6] = 1;
+    elf[18..20].copy_from_slice(&ELF_MACHINE_DPU.to_le_bytes());
+
+    let mut offsets = Vec::default();
+    for section in &sections {
+        offsets.push(elf.len() as u32);
+        elf.extend_from_slice(&section.content);
+    }
+
+    let section_table = elf.len() as u32;
+    for (section, offset) in sections.iter().zip(offsets) {
+        let size = if section.kind == SECTION_TYPE_NOBITS { 0x20 } else { section.content.len() as u32 };
+        for field in &[0, section.kind, section.flags, section.address, offset, size, section.link, 0, 4, 0] {
+            elf.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+
+    elf[32..36].copy_from_slice(&section_table.to_le_bytes());
+    elf[46..48].copy_from_slice(&40u16.to_le_bytes());
+    elf[48..50].copy_from_slice(&(sections.len() as u16).to_le_bytes());
+
+    elf
+}
+
+fn write_binary(name: &str, content: &[u8]) -> PathBuf {
+    let path = env::temp_dir().join(format!("dpu-cluster-core-{}-{}", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn can_load_a_program_from_an_elf_file() -> Result<(), ProgramError> {
+    let path = write_binary("program.elf", &build_elf(&[("main", 0x8000_0010, 16), ("buffer", 0x0800_0040, 8)]));
+
+    let program = Program::from_elf(&path)?;
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(Some(&vec![1, 2]), program.iram_sections().get(&2));
+    assert_eq!(Some(&vec![1, 2]), program.wram_sections().get(&2));
+    assert_eq!(Some(&vec![0xAA; 8]), program.mram_sections().get(&0x40));
+    assert_eq!((1, 1, 1), (program.iram_sections().len(), program.wram_sections().len(), program.mram_sections().len()));
+
+    assert_eq!(Some(&Symbol { address: 0x8000_0010, size: 16 }), program.symbol("main"));
+    assert_eq!(Some(&Symbol { address: 0x0800_0040, size: 8 }), program.symbol("buffer"));
+    assert_eq!(2, program.symbols().len());
+    assert_eq!(path.to_str(), program.binary_file());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_elf_files_are_rejected() {
+    let path = write_binary("invalid.elf", b"\x7fELF but not really");
+
+    match Program::from_elf(&path) {
+        Err(ProgramError::InvalidElf(_)) => (),
+        _ => panic!("a truncated file is not a valid program"),
+    }
+
+    fs::remove_file(&path).unwrap();
+
+    let mut elf = build_elf(&[]);
+    elf[18..20].copy_from_slice(&0x3eu16.to_le_bytes());
+    let path = write_binary("x86.elf", &elf);
+
+    match Program::from_elf(&path) {
+        Err(ProgramError::InvalidElf(_)) => (),
+        _ => panic!("a binary for another machine is not a valid program"),
+    }
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn symbols_are_resolved_in_their_memory() -> Result<(), ProgramError> {
+    let path = write_binary("symbols.elf", &build_elf(&[("main", 0x8000_0010, 16), ("counter", 0x0000_0008, 8), ("buffer", 0x0800_0040, 8), ("wrapping", 0xffff_fff8, 16)]));
+    let program = Program::from_elf(&path)?;
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(SymbolAddress::Mram(0x44), program.resolve("buffer", 4, 4).unwrap());
+    assert_eq!(SymbolAddress::Wram(0x9), program.resolve("counter", 1, 2).unwrap());
+    assert_eq!(SymbolAddress::Iram(0x10), program.resolve("main", 0, 16).unwrap());
+
+    match program.resolve("missing", 0, 0) {
+        Err(ClusterError::UnknownSymbol(symbol)) => assert_eq!("missing", symbol),
+        _ => panic!("the symbol is not defined by the program"),
+    }
+    match program.resolve("buffer", 4, 8) {
+        Err(ClusterError::SymbolOverflow { offset: 4, length: 8, size: 8, .. }) => (),
+        _ => panic!("the access does not fit in the symbol"),
+    }
+    match program.resolve("wrapping", 8, 8) {
+        Err(ClusterError::SymbolOverflow { offset: 8, length: 8, size: 16, .. }) => (),
+        _ => panic!("the access does not fit in the address space"),
+    }
+    match program.resolve_in_mram("counter", 0, 4) {
+        Err(ClusterError::InvalidSymbolLocation(_)) => (),
+        _ => panic!("the symbol is not in MRAM"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn programs_are_validated_against_the_rank_memories() {
+    let mut description = DpuRankDescription::default();
+    description.memories.iram_size = 16;
+    description.memories.wram_size = 8;
+    description.memories.mram_size = 64;
+
+    let sections = |iram: Vec<(u16, usize)>, wram: Vec<(u32, usize)>| Program::from_sections(
+        iram.into_iter().map(|(offset, length)| (offset, vec![0; length])).collect(),
+        wram.into_iter().map(|(offset, length)| (offset, vec![0; length])).collect(),
+        HashMap::default()
+    );
+
+    assert!(sections(vec![(0, 8), (8, 8)], vec![(0, 8)]).validate(&description).is_ok());
+
+    match sections(vec![(12, 8)], vec![]).validate(&description) {
+        Err(ClusterError::InvalidProgram(ProgramError::SectionOutOfBounds { memory: Memory::Iram, offset: 12, length: 8, size: 16 })) => (),
+        _ => panic!("the IRAM section does not fit in the IRAM"),
+    }
+    match sections(vec![], vec![(0, 4), (2, 4)]).validate(&description) {
+        Err(ClusterError::InvalidProgram(ProgramError::OverlappingSections { memory: Memory::Wram, first: 0, second: 2 })) => (),
+        _ => panic!("the WRAM sections overlap"),
+    }
+    match sections(vec![(0, 0x10000)], vec![]).validate(&description) {
+        Err(ClusterError::InvalidProgram(ProgramError::SectionTooLarge { memory: Memory::Iram, .. })) => (),
+        _ => panic!("the IRAM section length does not fit on 16 bits"),
+    }
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn invalid_programs_are_not_loaded() -> Result<(), ClusterError> {
+    use dpu_cluster_core::cluster::Cluster;
+    use dpu_cluster_core::config::ClusterConfiguration;
+    use dpu_cluster_core::view::View;
+
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+
+    match cluster.driver().load(&View::all(), &Program::new(vec![0; 1 << 16], vec![0; 16], None)) {
+        Err(ClusterError::InvalidProgram(ProgramError::SectionTooLarge { .. })) => Ok(()),
+        _ => panic!("the program does not fit in the IRAM"),
+    }
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn can_copy_to_and_from_symbols() -> Result<(), ClusterError> {
+    use dpu_cluster_core::cluster::Cluster;
+    use dpu_cluster_core::config::ClusterConfiguration;
+    use dpu_cluster_core::dpu::DpuId;
+    use dpu_cluster_core::memory::ToDpuTransfer;
+
+    let path = write_binary("copy.elf", &build_elf(&[("counter", 0x0000_0008, 8), ("buffer", 0x0800_0040, 8)]));
+    let program = Program::from_elf(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1))?;
+    let driver = cluster.driver();
+    let dpu = DpuId::new(0, 0, 0);
+
+    driver.copy_to_symbol(&dpu, &program, "counter", 0, &[1, 2, 3, 4, 5, 6, 7, 8])?;
+    driver.copy_to_symbol(&dpu, &program, "counter", 3, &[0xAA, 0xBB])?;
+    let mut counter = [0u8; 8];
+    driver.copy_from_symbol(&dpu, &program, "counter", 0, &mut counter)?;
+    assert_eq!([1, 2, 3, 0xAA, 0xBB, 6, 7, 8], counter);
+
+    let input = vec![1u8, 2, 3, 4];
+    let mut output = vec![0u8; 4];
+    match driver.copy_to_memory(&mut ToDpuTransfer::default().add_at_symbol(&program, dpu, "buffer", 4, input.as_slice())?) {
+        Err(ClusterError::UnalignedMramAccess { offset: 0x44, length: 4, .. }) => (),
+        _ => panic!("the region is not aligned on 8 bytes"),
+    }
+    driver.copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions().add_at_symbol(&program, dpu, "buffer", 4, input.as_slice())?)?;
+    driver.copy_from_symbol(&dpu, &program, "buffer", 4, &mut output)?;
+    assert_eq!(input, output);
+
+    Ok(())
+}
+
+#[cfg(feature = "mock")]
+#[test]
+fn mram_sections_are_loaded_with_the_program() -> Result<(), ClusterError> {
+    use dpu_cluster_core::cluster::Cluster;
+    use dpu_cluster_core::config::ClusterConfiguration;
+    use dpu_cluster_core::dpu::DpuId;
+    use dpu_cluster_core::memory::FromDpuTransfer;
+    use dpu_cluster_core::view::View;
+
+    let path = write_binary("mram.elf", &build_elf(&[]));
+    let program = Program::from_elf(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(2))?;
+    let dpu = DpuId::new(1, 0, 0);
+    let mut mram = vec![0u8; 8];
+
+    cluster.driver().load(&View::all(), &program)?;
+    cluster.driver().copy_from_memory(&mut FromDpuTransfer::default().add(dpu, 0x40, mram.as_mut_slice()))?;
+
+    assert_eq!(vec![0xAA; 8], mram);
+
+    Ok(())
+}
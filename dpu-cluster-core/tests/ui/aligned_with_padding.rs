@@ -0,0 +1,12 @@
+use dpu_cluster_core::pod::DpuPod;
+
+// the alignment pads the 12 bytes of the fields to 16
+#[derive(Clone, Copy, DpuPod)]
+#[repr(C, align(16))]
+struct Aligned {
+    key: u32,
+    length: u32,
+    flags: [u8; 4]
+}
+
+fn main() {}
@@ -0,0 +1,155 @@
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::dpu::ProcessId;
+use dpu_cluster_core::driver::Mergeable;
+use dpu_cluster_core::lease::LeasePolicy;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::memory::FromDpuTransfer;
+use dpu_cluster_core::memory::ToDpuTransfer;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::view::View;
+use dpu_cluster_client::protocol;
+use dpu_cluster_client::protocol::DaemonError;
+use dpu_cluster_client::protocol::Request;
+use dpu_cluster_client::protocol::Response;
+use dpu_cluster_client::protocol::PROTOCOL_VERSION;
+
+// Each connection is handled by its own thread, and is a distinct owner so that a process may
+// hold several independent sessions. The DPUs leased by a client are released when its
+// connection is closed.
+pub struct Daemon {
+    cluster: Cluster
+}
+
+impl Daemon {
+    pub fn new(cluster: Cluster) -> Self {
+        Daemon { cluster }
+    }
+
+    pub fn cluster(&self) -> &Cluster {
+        &self.cluster
+    }
+
+    pub fn serve(&self, listener: UnixListener) -> io::Result<()> {
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                scope.spawn(move || self.handle_connection(stream));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn handle_connection(&self, mut stream: UnixStream) {
+        let owner = match self.handshake(&mut stream) {
+            Some(owner) => owner,
+            None => return,
+        };
+
+        // a failing connection is closed, there is nobody left to report the error to
+        while let Ok(Some(request)) = protocol::receive(&mut stream) {
+            let response = self.handle(&owner, request).unwrap_or_else(Response::Error);
+
+            if protocol::send(&mut stream, &response).is_err() {
+                break;
+            }
+        }
+
+        self.cluster.release_all(&owner);
+    }
+
+    fn handshake(&self, stream: &mut UnixStream) -> Option<ProcessId> {
+        match protocol::receive(stream) {
+            Ok(Some(Request::Hello { version: PROTOCOL_VERSION })) => {
+                protocol::send(stream, &Response::Hello(PROTOCOL_VERSION)).ok()?;
+                Some(self.cluster.new_owner())
+            },
+            Ok(Some(Request::Hello { version })) => {
+                let _ = protocol::send(stream, &Response::Error(DaemonError::UnsupportedVersion(version)));
+                None
+            },
+            Ok(Some(_)) => {
+                let _ = protocol::send(stream, &Response::Error(DaemonError::InvalidRequest));
+                None
+            },
+            _ => None,
+        }
+    }
+
+    fn handle(&self, owner: &ProcessId, request: Request) -> Result<Response, DaemonError> {
+        let driver = self.cluster.driver();
+
+        match request {
+            Request::Hello { .. } => Err(DaemonError::InvalidRequest),
+            Request::Lease(nr_of_dpus) => Ok(Response::Leased(self.cluster.reserve(*owner, nr_of_dpus, LeasePolicy::default())?)),
+            Request::Release(dpus) => {
+                self.check_ownership(owner, &dpus)?;
+                self.cluster.release(owner, &dpus);
+                Ok(Response::Done)
+            },
+            Request::Load(dpus, iram_sections, wram_sections, mram_sections) => {
+                self.check_ownership(owner, &dpus)?;
+                let program = Program::from_sections(iram_sections, wram_sections, mram_sections);
+
+                for dpu in dpus {
+                    driver.load(&View::one(dpu), &program)?;
+                }
+
+                Ok(Response::Done)
+            },
+            Request::Boot(dpus) => {
+                self.check_ownership(owner, &dpus)?;
+
+                for dpu in dpus {
+                    driver.boot(&View::one(dpu))?;
+                }
+
+                Ok(Response::Done)
+            },
+            Request::Poll(dpus) => {
+                self.check_ownership(owner, &dpus)?;
+                let mut status = RunStatus::default();
+
+                for dpu in dpus {
+                    status = status.merge_with(&driver.fetch_status(&View::one(dpu))?);
+                }
+
+                Ok(Response::Status(status))
+            },
+            Request::CopyTo { dpu, offset, data } => {
+                self.check_ownership(owner, &[dpu])?;
+                self.check_mram_access(offset, data.len() as u32)?;
+                driver.copy_to_memory(&mut ToDpuTransfer::default().bouncing_unaligned_regions().add(dpu, offset, data.as_slice()))?;
+                Ok(Response::Done)
+            },
+            Request::CopyFrom { dpu, offset, length } => {
+                self.check_ownership(owner, &[dpu])?;
+                self.check_mram_access(offset, length)?;
+                let mut data = vec![0u8; length as usize];
+                driver.copy_from_memory(&mut FromDpuTransfer::default().bouncing_unaligned_regions().add(dpu, offset, data.as_mut_slice()))?;
+                Ok(Response::Data(data))
+            },
+        }
+    }
+
+    fn check_ownership(&self, owner: &ProcessId, dpus: &[DpuId]) -> Result<(), DaemonError> {
+        match dpus.iter().find(|dpu| self.cluster.owner_of(dpu).as_ref() != Some(owner)) {
+            Some(dpu) => Err(DaemonError::NotOwner(*dpu)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_mram_access(&self, offset: u32, length: u32) -> Result<(), DaemonError> {
+        let mram_size = self.cluster.driver().rank_description.memories.mram_size;
+
+        match offset.checked_add(length) {
+            Some(end) if end <= mram_size => Ok(()),
+            _ => Err(DaemonError::InvalidRequest),
+        }
+    }
+}
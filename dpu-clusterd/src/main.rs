@@ -0,0 +1,69 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::net::UnixListener;
+use std::process;
+use dpu_sys::DpuTarget;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_client::protocol::DEFAULT_SOCKET_PATH;
+use dpu_clusterd::daemon::Daemon;
+
+const USAGE: &str = "usage: dpu-clusterd [--simulator] [<socket path>]";
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum AppError {
+    SocketError(io::Error),
+    DpuError(ClusterError)
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::SocketError(err)
+    }
+}
+
+impl From<ClusterError> for AppError {
+    fn from(err: ClusterError) -> Self {
+        AppError::DpuError(err)
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{:?}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), AppError> {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let use_simulator = args.first().map(|arg| arg == "--simulator").unwrap_or(false);
+    if use_simulator {
+        args.remove(0);
+    }
+
+    let socket_path = args.first().map(String::as_str).unwrap_or(DEFAULT_SOCKET_PATH);
+
+    let target = if use_simulator {
+        DpuTarget::for_functional_simulator()
+    } else {
+        DpuTarget::for_hardware_implementation()
+    };
+    // the daemon manages all the DPUs of the machine
+    let cluster = Cluster::create(ClusterConfiguration { target, ..Default::default() })?;
+
+    // a previous instance may have left its socket behind
+    if fs::metadata(socket_path).is_ok() {
+        fs::remove_file(socket_path)?;
+    }
+    let listener = UnixListener::bind(socket_path)?;
+    println!("Listening on {}", socket_path);
+
+    Daemon::new(cluster).serve(listener)?;
+
+    Ok(())
+}
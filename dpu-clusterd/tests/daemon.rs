@@ -0,0 +1,100 @@
+#![cfg(feature = "mock")]
+
+use std::env;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_client::client::Client;
+use dpu_cluster_client::client::ClientError;
+use dpu_cluster_client::protocol::DaemonError;
+use dpu_clusterd::daemon::Daemon;
+
+fn start_daemon(name: &str, nr_of_dpus: u32) -> PathBuf {
+    let path = env::temp_dir().join(format!("dpu-clusterd-{}-{}.sock", name, process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(nr_of_dpus)).unwrap();
+    let listener = UnixListener::bind(&path).unwrap();
+    thread::spawn(move || Daemon::new(cluster).serve(listener));
+
+    path
+}
+
+#[test]
+fn can_run_a_program_on_leased_dpus() -> Result<(), ClientError> {
+    let path = start_daemon("run", 2);
+    let mut client = Client::connect(&path)?;
+
+    let dpus = client.lease(2)?;
+    assert_eq!(2, dpus.len());
+
+    client.load(&dpus, &Program::new(vec![0; 16], vec![0; 16], None))?;
+    client.copy_to(dpus[0], 64, &[1, 2, 3, 4, 5, 6, 7, 8])?;
+    client.boot(&dpus)?;
+
+    loop {
+        match client.poll(&dpus)? {
+            RunStatus::Running => (),
+            RunStatus::Idle => break,
+            RunStatus::Fault(faults) | RunStatus::TimedOut(faults) => panic!("unexpected faults: {:?}", faults),
+        }
+    }
+
+    assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8], client.copy_from(dpus[0], 64, 8)?);
+
+    Ok(())
+}
+
+#[test]
+fn dpus_are_owned_by_the_leasing_process() -> Result<(), ClientError> {
+    let path = start_daemon("ownership", 2);
+    let mut first = Client::connect(&path)?;
+    let mut second = Client::connect(&path)?;
+
+    let first_dpus = first.lease(1)?;
+    let second_dpus = second.lease(1)?;
+
+    match first.boot(&second_dpus) {
+        Err(ClientError::Daemon(DaemonError::NotOwner(dpu))) => assert_eq!(second_dpus[0], dpu),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    match second.lease(1) {
+        Err(ClientError::Daemon(DaemonError::NotEnoughResources { expected: 1, found: 0 })) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    first.release(&first_dpus)?;
+    assert_eq!(first_dpus, second.lease(1)?);
+
+    Ok(())
+}
+
+#[test]
+fn dpus_are_released_when_the_client_disconnects() -> Result<(), ClientError> {
+    let path = start_daemon("disconnect", 1);
+    let mut first = Client::connect(&path)?;
+    first.lease(1)?;
+    drop(first);
+
+    let mut second = Client::connect(&path)?;
+
+    for _ in 0..100 {
+        match second.lease(1) {
+            Ok(dpus) => {
+                assert_eq!(1, dpus.len());
+                return Ok(());
+            },
+            Err(ClientError::Daemon(DaemonError::NotEnoughResources { .. })) => thread::sleep(Duration::from_millis(10)),
+            Err(err) => return Err(err),
+        }
+    }
+
+    panic!("the dpu has not been released");
+}
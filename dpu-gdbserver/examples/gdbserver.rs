@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::net::TcpListener;
+use std::process;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::view::View;
+use dpu_gdbserver::server::GdbServer;
+use dpu_gdbserver::server::GdbServerError;
+
+const USAGE: &str = "usage: gdbserver [--simulator] <port> <rank> <slice> <member> [<iram file> <wram file>]";
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum AppError {
+    InvalidArguments,
+    FileManagementError(io::Error),
+    DpuError(ClusterError),
+    ServerError(GdbServerError)
+}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::FileManagementError(err)
+    }
+}
+
+impl From<ClusterError> for AppError {
+    fn from(err: ClusterError) -> Self {
+        AppError::DpuError(err)
+    }
+}
+
+impl From<GdbServerError> for AppError {
+    fn from(err: GdbServerError) -> Self {
+        AppError::ServerError(err)
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{:?}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), AppError> {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let use_simulator = args.first().map(|arg| arg == "--simulator").unwrap_or(false);
+    if use_simulator {
+        args.remove(0);
+    }
+
+    let numbers = args.iter().take(4).map(|arg| arg.parse::<u16>()).collect::<Result<Vec<_>, _>>()
+        .map_err(|_| AppError::InvalidArguments)?;
+
+    let (port, dpu) = match numbers.as_slice() {
+        [port, rank, slice, member] => (*port, DpuId::new(*rank as u8, *slice as u8, *member as u8)),
+        _ => return Err(AppError::InvalidArguments),
+    };
+
+    let (rank, _, _) = dpu.members();
+    let config = if use_simulator {
+        ClusterConfiguration::for_functional_simulator(1)
+    } else {
+        ClusterConfiguration::for_hardware(1)
+    };
+    let cluster = Cluster::create(config)?;
+    let (nr_of_ranks, _, _) = cluster.topology();
+    if rank >= nr_of_ranks {
+        return Err(AppError::InvalidArguments);
+    }
+
+    if let [_, _, _, _, iram_file, wram_file] = args.as_slice() {
+        let program = Program::new_raw(fs::read(iram_file)?, fs::read(wram_file)?);
+        let view = View::one(dpu);
+        cluster.driver().load(&view, &program)?;
+        cluster.driver().boot(&view)?;
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Listening on port {} for DPU {}", port, dpu);
+    let (stream, _) = listener.accept()?;
+
+    GdbServer::attach(&cluster, dpu)?.serve(stream)?;
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpStream;
+
+const INTERRUPT: u8 = 0x03;
+
+pub enum Incoming {
+    Packet(String),
+    Interrupt
+}
+
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let writer = stream.try_clone()?;
+        let reader = BufReader::new(stream);
+
+        Ok(Connection { reader, writer })
+    }
+
+    // returns None when the client closed the connection
+    pub fn receive(&mut self) -> io::Result<Option<Incoming>> {
+        loop {
+            let byte = match self.read_byte()? {
+                Some(byte) => byte,
+                None => return Ok(None),
+            };
+
+            match byte {
+                b'$' => {
+                    if let Some(packet) = self.read_packet_body()? {
+                        return Ok(Some(Incoming::Packet(packet)));
+                    }
+                },
+                INTERRUPT => return Ok(Some(Incoming::Interrupt)),
+                // acknowledgments and line noise
+                _ => (),
+            }
+        }
+    }
+
+    pub fn send(&mut self, data: &str) -> io::Result<()> {
+        write!(self.writer, "${}#{:02x}", data, checksum(data.as_bytes()))?;
+        self.writer.flush()
+    }
+
+    pub fn is_interrupted(&mut self) -> io::Result<bool> {
+        if !self.reader.buffer().is_empty() {
+            return Ok(self.reader.buffer().contains(&INTERRUPT));
+        }
+
+        self.reader.get_ref().set_nonblocking(true)?;
+        let result = self.reader.fill_buf().map(|buffer| buffer.contains(&INTERRUPT));
+        self.reader.get_ref().set_nonblocking(false)?;
+
+        match result {
+            Ok(interrupted) => Ok(interrupted),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+
+        match self.reader.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    // returns None when the checksum does not match, in which case a retransmission is requested
+    fn read_packet_body(&mut self) -> io::Result<Option<String>> {
+        let mut body = Vec::default();
+        self.reader.read_until(b'#', &mut body)?;
+        body.pop();
+
+        let mut expected = [0u8; 2];
+        self.reader.read_exact(&mut expected)?;
+
+        let expected = std::str::from_utf8(&expected).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        if expected == Some(checksum(&body)) {
+            self.writer.write_all(b"+")?;
+            Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+        } else {
+            self.writer.write_all(b"-")?;
+            Ok(None)
+        }
+    }
+}
+
+pub fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |acc, byte| acc.wrapping_add(*byte))
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| hex.get(idx..(idx + 2)).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect()
+}
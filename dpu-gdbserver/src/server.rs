@@ -0,0 +1,453 @@
+use std::convert::TryInto;
+use std::io;
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::debugger::Debugger;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::driver::RunStatus;
+use dpu_cluster_core::error::ClusterError;
+use dpu_cluster_core::program::IRAM_ADDRESS_SPACE as IRAM_BASE;
+use dpu_cluster_core::program::MRAM_ADDRESS_SPACE as MRAM_BASE;
+use dpu_cluster_core::program::WRAM_ADDRESS_SPACE as WRAM_BASE;
+use dpu_cluster_core::view::View;
+use dpu_sys::DpuMemories;
+use crate::packet::Connection;
+use crate::packet::Incoming;
+use crate::packet::decode_hex;
+use crate::packet::encode_hex;
+
+const INSTRUCTION_SIZE: u32 = 8;
+const WORD_SIZE: u32 = 4;
+
+const PACKET_SIZE: u32 = 0x4000;
+// memory is exchanged in hexadecimal: two characters per byte must fit in a packet
+const MAX_MEMORY_ACCESS: u32 = PACKET_SIZE / 2;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug)]
+pub enum GdbServerError {
+    Io(io::Error),
+    Cluster(ClusterError)
+}
+
+impl From<io::Error> for GdbServerError {
+    fn from(err: io::Error) -> Self {
+        GdbServerError::Io(err)
+    }
+}
+
+impl From<ClusterError> for GdbServerError {
+    fn from(err: ClusterError) -> Self {
+        GdbServerError::Cluster(err)
+    }
+}
+
+enum Reply {
+    Send(String),
+    Close(Option<String>)
+}
+
+// GDB thread ids start at 1, DPU thread ids at 0
+pub struct GdbServer<'a> {
+    cluster: &'a Cluster,
+    dpu: DpuId,
+    debugger: Option<Debugger<'a>>,
+    thread: u8
+}
+
+impl <'a> GdbServer<'a> {
+    pub fn attach(cluster: &'a Cluster, dpu: DpuId) -> Result<Self, GdbServerError> {
+        let debugger = cluster.debug(dpu)?;
+        let thread = debugger.report().thread.or_else(|| debugger.running_threads().first().copied()).unwrap_or(0);
+
+        Ok(GdbServer { cluster, dpu, debugger: Some(debugger), thread })
+    }
+
+    pub fn serve(&mut self, stream: TcpStream) -> Result<(), GdbServerError> {
+        let mut connection = Connection::new(stream)?;
+
+        while let Some(incoming) = connection.receive()? {
+            let packet = match incoming {
+                Incoming::Packet(packet) => packet,
+                // the DPU is already halted
+                Incoming::Interrupt => continue,
+            };
+
+            match self.handle(&packet, &mut connection)? {
+                Reply::Send(reply) => connection.send(&reply)?,
+                Reply::Close(reply) => {
+                    if let Some(reply) = reply {
+                        connection.send(&reply)?;
+                    }
+                    return Ok(());
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: &str, connection: &mut Connection) -> Result<Reply, GdbServerError> {
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, |c| c.len_utf8()));
+
+        let reply = match command {
+            "?" => self.stop_reply(SIGTRAP),
+            "q" => self.query(arguments),
+            "H" => self.select_thread(arguments),
+            "T" => self.is_thread_alive(arguments),
+            "g" => self.read_registers(),
+            "G" => self.write_registers(arguments),
+            "p" => self.read_register(arguments),
+            "P" => self.write_register(arguments),
+            "m" => self.read_memory(arguments),
+            "M" => self.write_memory(arguments),
+            "s" => self.step(),
+            "c" => self.resume(connection)?,
+            "D" => return self.detach().map(|_| Reply::Close(Some("OK".to_string()))),
+            "k" => return Ok(Reply::Close(None)),
+            _ => String::default(),
+        };
+
+        Ok(Reply::Send(reply))
+    }
+
+    fn debugger(&mut self) -> &mut Debugger<'a> {
+        // unwrap: the debugger is only released while the DPU runs, and restored before handling the next packet
+        self.debugger.as_mut().unwrap()
+    }
+
+    fn stop_reply(&mut self, signal: u8) -> String {
+        if let Some(thread) = self.debugger().report().thread {
+            self.thread = thread;
+        }
+
+        format!("T{:02x}thread:{:x};", signal, self.thread as u32 + 1)
+    }
+
+    fn query(&mut self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            format!("PacketSize={:x}", PACKET_SIZE)
+        } else if query == "Attached" {
+            "1".to_string()
+        } else if query == "C" {
+            format!("QC{:x}", self.thread as u32 + 1)
+        } else if query == "fThreadInfo" {
+            let threads = self.debugger().running_threads();
+            let threads = if threads.is_empty() { vec![self.thread] } else { threads };
+            let ids = threads.iter().map(|thread| format!("{:x}", *thread as u32 + 1)).collect::<Vec<_>>();
+
+            format!("m{}", ids.join(","))
+        } else if query == "sThreadInfo" {
+            "l".to_string()
+        } else {
+            String::default()
+        }
+    }
+
+    fn select_thread(&mut self, arguments: &str) -> String {
+        // the first character selects the operation (c or g), which share the same thread here
+        match arguments.get(1..).and_then(parse_thread_id) {
+            Some(Some(thread)) if self.is_valid_thread(thread) => {
+                self.thread = thread;
+                "OK".to_string()
+            },
+            Some(None) => "OK".to_string(),
+            _ => error_reply(),
+        }
+    }
+
+    fn is_thread_alive(&mut self, arguments: &str) -> String {
+        match parse_thread_id(arguments) {
+            Some(Some(thread)) if self.is_valid_thread(thread) => "OK".to_string(),
+            _ => error_reply(),
+        }
+    }
+
+    fn is_valid_thread(&mut self, thread: u8) -> bool {
+        (thread as usize) < self.debugger().context().pcs.len()
+    }
+
+    fn read_registers(&mut self) -> String {
+        let thread = self.thread;
+        let debugger = self.debugger();
+
+        match (debugger.registers(thread), debugger.pc(thread)) {
+            (Some(registers), Some(pc)) => {
+                let mut reply: String = registers.iter().map(|register| encode_hex(&register.to_le_bytes())).collect();
+                reply.push_str(&encode_hex(&pc_to_address(pc).to_le_bytes()));
+                reply
+            },
+            _ => error_reply(),
+        }
+    }
+
+    fn write_registers(&mut self, arguments: &str) -> String {
+        let thread = self.thread;
+        let values = match decode_hex(arguments) {
+            Some(bytes) => bytes.chunks(WORD_SIZE as usize).filter_map(u32_from_le_bytes).collect::<Vec<_>>(),
+            None => return error_reply(),
+        };
+
+        let debugger = self.debugger();
+        let registers = match debugger.registers_mut(thread) {
+            Some(registers) => registers,
+            None => return error_reply(),
+        };
+        let nr_of_registers = registers.len();
+
+        for (register, value) in registers.iter_mut().zip(&values) {
+            *register = *value;
+        }
+
+        match values.get(nr_of_registers).map(|pc| debugger.set_pc(thread, address_to_pc(*pc))) {
+            Some(Err(_)) => error_reply(),
+            _ => "OK".to_string(),
+        }
+    }
+
+    fn read_register(&mut self, arguments: &str) -> String {
+        let thread = self.thread;
+        let debugger = self.debugger();
+        let (registers, pc) = match (debugger.registers(thread), debugger.pc(thread)) {
+            (Some(registers), Some(pc)) => (registers, pc),
+            _ => return error_reply(),
+        };
+
+        match usize::from_str_radix(arguments, 16) {
+            Ok(register) if register < registers.len() => encode_hex(&registers[register].to_le_bytes()),
+            Ok(register) if register == registers.len() => encode_hex(&pc_to_address(pc).to_le_bytes()),
+            _ => error_reply(),
+        }
+    }
+
+    fn write_register(&mut self, arguments: &str) -> String {
+        let thread = self.thread;
+        let parsed = arguments.split_once('=')
+            .and_then(|(register, value)| Some((usize::from_str_radix(register, 16).ok()?, u32_from_le_bytes(&decode_hex(value)?)?)));
+
+        let debugger = self.debugger();
+        let nr_of_registers = match debugger.registers(thread) {
+            Some(registers) => registers.len(),
+            None => return error_reply(),
+        };
+
+        let written = match parsed {
+            Some((register, value)) if register < nr_of_registers => debugger.registers_mut(thread).map(|registers| registers[register] = value),
+            Some((register, value)) if register == nr_of_registers => debugger.set_pc(thread, address_to_pc(value)).ok(),
+            _ => None,
+        };
+
+        match written {
+            Some(()) => "OK".to_string(),
+            None => error_reply(),
+        }
+    }
+
+    fn read_memory(&mut self, arguments: &str) -> String {
+        let memories = &self.cluster.driver().rank_description.memories;
+
+        match parse_memory_range(arguments).map(|(address, length)| read_memory(self.debugger(), memories, address, length)) {
+            Some(Ok(data)) => encode_hex(&data),
+            _ => error_reply(),
+        }
+    }
+
+    fn write_memory(&mut self, arguments: &str) -> String {
+        let parsed = arguments.split_once(':')
+            .and_then(|(range, data)| Some((parse_memory_range(range)?, decode_hex(data)?)));
+
+        match parsed {
+            Some(((address, length), data)) if data.len() == length as usize => {
+                let memories = &self.cluster.driver().rank_description.memories;
+
+                match write_memory(self.debugger(), memories, address, &data) {
+                    Ok(()) => "OK".to_string(),
+                    Err(_) => error_reply(),
+                }
+            },
+            _ => error_reply(),
+        }
+    }
+
+    fn step(&mut self) -> String {
+        let thread = self.thread;
+
+        match self.debugger().step(thread) {
+            Ok(()) => format!("T{:02x}thread:{:x};", SIGTRAP, thread as u32 + 1),
+            Err(_) => error_reply(),
+        }
+    }
+
+    fn resume(&mut self, connection: &mut Connection) -> Result<String, GdbServerError> {
+        let view = View::one(self.dpu);
+
+        if let Some(debugger) = self.debugger.take() {
+            if debugger.resume().is_err() {
+                self.debugger = Some(self.cluster.debug(self.dpu)?);
+                return Ok(error_reply());
+            }
+        }
+
+        loop {
+            match self.cluster.driver().fetch_status(&view)? {
+                RunStatus::Running => {
+                    if connection.is_interrupted()? {
+                        self.debugger = Some(self.cluster.debug(self.dpu)?);
+                        return Ok(self.stop_reply(SIGINT));
+                    }
+
+                    thread::sleep(POLL_INTERVAL);
+                },
+                RunStatus::Fault(_) | RunStatus::TimedOut(_) => {
+                    self.debugger = Some(self.cluster.debug(self.dpu)?);
+                    return Ok(self.stop_reply(SIGTRAP));
+                },
+                RunStatus::Idle => {
+                    self.debugger = Some(self.cluster.debug(self.dpu)?);
+                    return Ok("W00".to_string());
+                },
+            }
+        }
+    }
+
+    fn detach(&mut self) -> Result<(), GdbServerError> {
+        if let Some(debugger) = self.debugger.take() {
+            debugger.resume()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn error_reply() -> String {
+    "E01".to_string()
+}
+
+// None stands for "any thread"
+fn parse_thread_id(id: &str) -> Option<Option<u8>> {
+    match i64::from_str_radix(id, 16).ok()? {
+        -1 | 0 => Some(None),
+        id if id <= (u8::MAX as i64) + 1 => Some(Some((id - 1) as u8)),
+        _ => None,
+    }
+}
+
+fn parse_memory_range(range: &str) -> Option<(u32, u32)> {
+    let (address, length) = range.split_once(',')?;
+    let length = u32::from_str_radix(length, 16).ok().filter(|length| *length <= MAX_MEMORY_ACCESS)?;
+
+    Some((u32::from_str_radix(address, 16).ok()?, length))
+}
+
+fn u32_from_le_bytes(bytes: &[u8]) -> Option<u32> {
+    bytes.try_into().ok().map(u32::from_le_bytes)
+}
+
+fn pc_to_address(pc: u16) -> u32 {
+    IRAM_BASE + (pc as u32) * INSTRUCTION_SIZE
+}
+
+fn address_to_pc(address: u32) -> u16 {
+    (address.wrapping_sub(IRAM_BASE) / INSTRUCTION_SIZE) as u16
+}
+
+// IRAM and WRAM are accessed by instructions and words: unaligned accesses are widened
+fn aligned_range(offset: u32, length: usize, alignment: u32) -> (u32, usize) {
+    let start = offset / alignment;
+    let end = (offset as usize + length).div_ceil(alignment as usize);
+
+    (start, end - start as usize)
+}
+
+// the memory sizes are given in instructions and words
+fn iram_range(debugger: &Debugger<'_>, memories: &DpuMemories, offset: u32, length: usize) -> Result<(u16, usize), ClusterError> {
+    check_range(debugger, offset, length, (memories.iram_size as u32).saturating_mul(INSTRUCTION_SIZE))?;
+
+    let (start, nr_of_instructions) = aligned_range(offset, length, INSTRUCTION_SIZE);
+    // unwrap: the range has been checked against the IRAM size, which is indexed on 16 bits
+    Ok((start.try_into().unwrap(), nr_of_instructions))
+}
+
+fn wram_range(debugger: &Debugger<'_>, memories: &DpuMemories, offset: u32, length: usize) -> Result<(u32, usize), ClusterError> {
+    check_range(debugger, offset, length, memories.wram_size.saturating_mul(WORD_SIZE))?;
+
+    Ok(aligned_range(offset, length, WORD_SIZE))
+}
+
+fn check_range(debugger: &Debugger<'_>, offset: u32, length: usize, limit: u32) -> Result<(), ClusterError> {
+    if (offset as u64) + (length as u64) > limit as u64 {
+        Err(ClusterError::OutOfBounds { dpu: debugger.dpu(), offset, length: length as u32, limit })
+    } else {
+        Ok(())
+    }
+}
+
+fn read_memory(debugger: &Debugger<'_>, memories: &DpuMemories, address: u32, length: u32) -> Result<Vec<u8>, ClusterError> {
+    let length = length as usize;
+
+    if address >= IRAM_BASE {
+        let offset = address - IRAM_BASE;
+        let (start, nr_of_instructions) = iram_range(debugger, memories, offset, length)?;
+        let mut instructions = vec![0u64; nr_of_instructions];
+        debugger.read_iram(start, &mut instructions)?;
+
+        let bytes = instructions.iter().flat_map(|instruction| instruction.to_le_bytes()).collect::<Vec<_>>();
+        let skip = (offset % INSTRUCTION_SIZE) as usize;
+        Ok(bytes[skip..(skip + length)].to_vec())
+    } else if address >= MRAM_BASE {
+        let mut data = vec![0u8; length];
+        debugger.read_mram(address - MRAM_BASE, &mut data)?;
+        Ok(data)
+    } else {
+        let offset = address - WRAM_BASE;
+        let (start, nr_of_words) = wram_range(debugger, memories, offset, length)?;
+        let mut words = vec![0u32; nr_of_words];
+        debugger.read_wram(start, &mut words)?;
+
+        let bytes = words.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>();
+        let skip = (offset % WORD_SIZE) as usize;
+        Ok(bytes[skip..(skip + length)].to_vec())
+    }
+}
+
+fn write_memory(debugger: &Debugger<'_>, memories: &DpuMemories, address: u32, data: &[u8]) -> Result<(), ClusterError> {
+    if address >= IRAM_BASE {
+        let offset = address - IRAM_BASE;
+        let (start, nr_of_instructions) = iram_range(debugger, memories, offset, data.len())?;
+        let mut instructions = vec![0u64; nr_of_instructions];
+        debugger.read_iram(start, &mut instructions)?;
+
+        let mut bytes = instructions.iter().flat_map(|instruction| instruction.to_le_bytes()).collect::<Vec<_>>();
+        let skip = (offset % INSTRUCTION_SIZE) as usize;
+        bytes[skip..(skip + data.len())].copy_from_slice(data);
+
+        let instructions = bytes.chunks(INSTRUCTION_SIZE as usize)
+            // unwrap: chunks have the exact instruction size
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        debugger.write_iram(start, &instructions)
+    } else if address >= MRAM_BASE {
+        debugger.write_mram(address - MRAM_BASE, data)
+    } else {
+        let offset = address - WRAM_BASE;
+        let (start, nr_of_words) = wram_range(debugger, memories, offset, data.len())?;
+        let mut words = vec![0u32; nr_of_words];
+        debugger.read_wram(start, &mut words)?;
+
+        let mut bytes = words.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<_>>();
+        let skip = (offset % WORD_SIZE) as usize;
+        bytes[skip..(skip + data.len())].copy_from_slice(data);
+
+        let words = bytes.chunks(WORD_SIZE as usize)
+            // unwrap: chunks have the exact word size
+            .map(|chunk| u32_from_le_bytes(chunk).unwrap())
+            .collect::<Vec<_>>();
+        debugger.write_wram(start, &words)
+    }
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "mock")]
+
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::thread;
+use dpu_cluster_core::cluster::Cluster;
+use dpu_cluster_core::config::ClusterConfiguration;
+use dpu_cluster_core::dpu::DpuId;
+use dpu_cluster_core::program::Program;
+use dpu_cluster_core::view::View;
+use dpu_gdbserver::packet::checksum;
+use dpu_gdbserver::server::GdbServer;
+
+fn exchange(stream: &mut TcpStream, packet: &str) -> String {
+    write!(stream, "${}#{:02x}", packet, checksum(packet.as_bytes())).unwrap();
+
+    let mut reply = Vec::default();
+    let mut byte = [0u8];
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        match byte[0] {
+            b'+' => (),
+            b'$' => (),
+            b'#' => break,
+            other => reply.push(other),
+        }
+    }
+    let mut checksum = [0u8; 2];
+    stream.read_exact(&mut checksum).unwrap();
+    stream.write_all(b"+").unwrap();
+
+    String::from_utf8(reply).unwrap()
+}
+
+#[test]
+fn can_debug_a_dpu_through_gdb() {
+    let cluster = Cluster::create(ClusterConfiguration::for_functional_simulator(1)).unwrap();
+    let dpu = DpuId::new(0, 0, 0);
+    let view = View::one(dpu);
+    cluster.driver().load(&view, &Program::new(vec![0x1122_3344_5566; 4], vec![0; 16], None)).unwrap();
+    cluster.driver().boot(&view).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    thread::scope(|scope| {
+        let server = scope.spawn(|| {
+            let (stream, _) = listener.accept().unwrap();
+            GdbServer::attach(&cluster, dpu).unwrap().serve(stream).unwrap();
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+
+        assert_eq!("T05thread:1;", exchange(&mut client, "?"));
+        assert_eq!("m1", exchange(&mut client, "qfThreadInfo"));
+        assert_eq!("l", exchange(&mut client, "qsThreadInfo"));
+
+        assert_eq!("00000080", exchange(&mut client, "p18"));
+        assert_eq!("T05thread:1;", exchange(&mut client, "s"));
+        assert_eq!("08000080", exchange(&mut client, "p18"));
+
+        assert_eq!("OK", exchange(&mut client, "P3=efbeadde"));
+        assert_eq!("efbeadde", exchange(&mut client, "p3"));
+
+        assert_eq!("6655443322", exchange(&mut client, "m80000008,5"));
+        assert_eq!("OK", exchange(&mut client, "M80000009,1:aa"));
+        assert_eq!("66aa443322110000", exchange(&mut client, "m80000008,8"));
+
+        assert_eq!("OK", exchange(&mut client, "M13,2:abcd"));
+        assert_eq!("00abcd00", exchange(&mut client, "m12,4"));
+
+        assert_eq!("OK", exchange(&mut client, "M8000000,3:010203"));
+        assert_eq!("010203", exchange(&mut client, "m8000000,3"));
+
+        assert_eq!("E01", exchange(&mut client, "m80080000,8"));
+        assert_eq!("E01", exchange(&mut client, "M8007fffc,8:0000000000000000"));
+        assert_eq!("E01", exchange(&mut client, "m8000000,2001"));
+
+        // the mock has 4096 instructions of IRAM and 16K words of WRAM
+        assert_ne!("E01", exchange(&mut client, "m80007ff8,8"));
+        assert_eq!("E01", exchange(&mut client, "m80008000,8"));
+        assert_eq!("E01", exchange(&mut client, "M80007ffc,8:0000000000000000"));
+        assert_ne!("E01", exchange(&mut client, "mfffc,4"));
+        assert_eq!("E01", exchange(&mut client, "mfffe,4"));
+        assert_eq!("E01", exchange(&mut client, "M10000,4:00000000"));
+
+        assert_eq!("W00", exchange(&mut client, "c"));
+        assert_eq!("OK", exchange(&mut client, "D"));
+
+        server.join().unwrap();
+    });
+}
@@ -0,0 +1,78 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Fields;
+use syn::parenthesized;
+use syn::parse_macro_input;
+use syn::token;
+
+// The layout is checked when the deriving crate is compiled: the fields must be DpuPod and leave no padding bytes.
+#[proc_macro_derive(DpuPod)]
+pub fn derive_dpu_pod(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "DpuPod cannot be derived for generic types"));
+    }
+
+    if !has_stable_layout(input)? {
+        return Err(Error::new(Span::call_site(), "DpuPod requires #[repr(C)] or #[repr(transparent)]"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().map(|field| &field.ty).collect::<Vec<_>>(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().map(|field| &field.ty).collect::<Vec<_>>(),
+            Fields::Unit => Vec::default(),
+        },
+        _ => return Err(Error::new(Span::call_site(), "DpuPod can only be derived for structs")),
+    };
+
+    let padding_message = format!("{} has padding bytes, which cannot be transferred to a DPU", name);
+
+    Ok(quote! {
+        unsafe impl ::dpu_cluster_core::pod::DpuPod for #name {}
+
+        const _: fn() = || {
+            fn check<T: ::dpu_cluster_core::pod::DpuPod>() {}
+            #( check::<#fields>(); )*
+        };
+
+        const _: () = assert!(::core::mem::size_of::<#name>() == 0 #( + ::core::mem::size_of::<#fields>() )*, #padding_message);
+    })
+}
+
+fn has_stable_layout(input: &DeriveInput) -> Result<bool, Error> {
+    let mut is_stable = false;
+
+    for attribute in input.attrs.iter().filter(|attribute| attribute.path().is_ident("repr")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                is_stable = true;
+            }
+            // align(N) and packed(N) carry arguments, which do not matter here
+            if meta.input.peek(token::Paren) {
+                let arguments;
+                parenthesized!(arguments in meta.input);
+                arguments.parse::<proc_macro2::TokenStream>()?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(is_stable)
+}
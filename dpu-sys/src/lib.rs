@@ -1,7 +1,15 @@
-use libc::{c_void, c_uchar, c_char, c_uint, c_ushort, c_ulong};
+use libc::c_void;
+#[cfg(not(feature = "mock"))]
+use libc::{c_uchar, c_char, c_uint, c_ushort, c_ulong};
 use std::ffi::CString;
 use std::collections::HashMap;
 use std::vec::Vec;
+use std::mem::MaybeUninit;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+use crate::mock::*;
 
 // todo: we should try to generate the CNI interface. Maybe check bindgen (https://github.com/rust-lang/rust-bindgen)
 
@@ -29,14 +37,14 @@ pub enum CniStatus {
 #[derive(Debug, Clone)]
 pub struct DpuError(pub CniStatus);
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[repr(C)]
 pub struct DpuSignature {
     pub config_id: u32,
     pub chip_id: u32
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[repr(C)]
 pub struct DpuStaticConfiguration {
     pub cmd_duration: u8,
@@ -48,7 +56,7 @@ pub struct DpuStaticConfiguration {
     pub clock_division: u8
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq)]
 #[repr(C)]
 pub struct DpuTopology {
     pub nr_of_control_interfaces: u8,
@@ -64,7 +72,7 @@ pub struct DpuMemoryRepair {
     wram_repair: *mut c_void
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[repr(C)]
 pub struct DpuMemories {
     pub mram_size: u32,
@@ -75,7 +83,7 @@ pub struct DpuMemories {
     pub cycle_accurate: bool
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[repr(C)]
 pub struct DpuInfo {
     pub nr_of_threads: u8,
@@ -124,6 +132,77 @@ struct RawDpuDebugContext {
     mem_fault_thread_index: u8
 }
 
+impl Default for DpuMemoryRepair {
+    fn default() -> Self {
+        DpuMemoryRepair {
+            do_iram_repair: false,
+            do_wram_repair: false,
+            iram_repair: std::ptr::null_mut(),
+            wram_repair: std::ptr::null_mut()
+        }
+    }
+}
+
+impl Default for DpuRankDescription {
+    fn default() -> Self {
+        DpuRankDescription {
+            signature: Default::default(),
+            static_config: Default::default(),
+            topology: Default::default(),
+            memories: Default::default(),
+            info: Default::default(),
+            _internals: std::ptr::null(),
+            _free_internals: std::ptr::null()
+        }
+    }
+}
+
+impl DpuTopology {
+    pub fn nr_of_dpus(&self) -> u32 {
+        (self.nr_of_control_interfaces as u32) * (self.nr_of_dpus_per_control_interface as u32)
+    }
+}
+
+// one bitfield per control interface, one bit per member
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct RankBitmap {
+    slices: Vec<u32>,
+    nr_of_dpus_per_control_interface: u8
+}
+
+impl RankBitmap {
+    pub fn for_topology(topology: &DpuTopology) -> Self {
+        RankBitmap {
+            slices: vec![0; topology.nr_of_control_interfaces as usize],
+            nr_of_dpus_per_control_interface: topology.nr_of_dpus_per_control_interface
+        }
+    }
+
+    pub fn set(&mut self, slice_id: u8, member_id: u8) {
+        assert!(member_id < self.nr_of_dpus_per_control_interface, "invalid member id {}", member_id);
+        self.slices[slice_id as usize] |= 1 << (member_id as u32);
+    }
+
+    pub fn is_set(&self, slice_id: u8, member_id: u8) -> bool {
+        member_id < self.nr_of_dpus_per_control_interface &&
+            self.slices.get(slice_id as usize).is_some_and(|slice| (slice & (1 << (member_id as u32))) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(u8, u8)> + '_ {
+        let nr_of_members = self.nr_of_dpus_per_control_interface;
+
+        self.slices.iter().enumerate().flat_map(move |(slice_id, slice)| {
+            (0..nr_of_members)
+                .filter(move |member_id| (slice & (1 << (*member_id as u32))) != 0)
+                .map(move |member_id| (slice_id as u8, member_id))
+        })
+    }
+}
+
 unsafe impl Send for DpuRankDescription {}
 unsafe impl Sync for DpuRankDescription {}
 
@@ -156,10 +235,39 @@ impl DpuDebugContext {
 
         DpuDebugContext { registers, pcs, atomic_register, zero_flags, carry_flags, scheduling, raw }
     }
+
+    pub fn nr_of_running_threads(&self) -> u8 {
+        self.raw.nr_of_running_threads
+    }
+
+    // the fault getters return the index of the faulting thread
+    pub fn breakpoint_fault(&self) -> Option<u8> {
+        if self.raw.bkp_fault { Some(self.raw.bkp_fault_thread_index) } else { None }
+    }
+
+    pub fn dma_fault(&self) -> Option<u8> {
+        if self.raw.dma_fault { Some(self.raw.dma_fault_thread_index) } else { None }
+    }
+
+    pub fn memory_fault(&self) -> Option<u8> {
+        if self.raw.mem_fault { Some(self.raw.mem_fault_thread_index) } else { None }
+    }
+
+    // lets the backends written for tests report any fault, as the mock only raises breakpoints
+    #[cfg(feature = "mock")]
+    pub fn set_faults(&mut self, breakpoint: Option<u8>, dma: Option<u8>, memory: Option<u8>) {
+        self.raw.bkp_fault = breakpoint.is_some();
+        self.raw.bkp_fault_thread_index = breakpoint.unwrap_or(0);
+        self.raw.dma_fault = dma.is_some();
+        self.raw.dma_fault_thread_index = dma.unwrap_or(0);
+        self.raw.mem_fault = memory.is_some();
+        self.raw.mem_fault_thread_index = memory.unwrap_or(0);
+    }
 }
 
+#[cfg(not(feature = "mock"))]
 #[link(name = "dpucni")]
-extern {
+extern "C" {
     fn dpu_cni_get_profile_description(backend: DpuType, profile: *const c_char, description: *mut DpuRankDescription) -> CniStatus;
     fn dpu_cni_get_rank_of_type(backend: DpuType, profile: *const c_char, link: *mut*const c_void) -> CniStatus;
     fn dpu_cni_free_rank(link: *const c_void) -> CniStatus;
@@ -183,10 +291,10 @@ extern {
     fn dpu_cni_copy_to_wram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, to: c_uint, source: *const c_uint, length: c_uint) -> CniStatus;
     fn dpu_cni_copy_from_wram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, destination: *mut c_uint, from: c_uint, length: c_uint) -> CniStatus;
     fn dpu_cni_transfer_matrix_allocate(link: *const c_void, matrix: *mut*mut c_void) -> CniStatus;
-    fn dpu_cni_transfer_matrix_free(link: *const c_void, matrix: *mut c_void) -> ();
-    fn dpu_cni_transfer_matrix_add_dpu(link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar, buffer: *mut c_uchar, length: c_uint, offset: c_uint, mram_number: c_uint) -> ();
-    fn dpu_cni_transfer_matrix_clear_dpu(link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar) -> ();
-    fn dpu_cni_transfer_matrix_clear_all(link: *const c_void, matrix: *mut c_void) -> ();
+    fn dpu_cni_transfer_matrix_free(link: *const c_void, matrix: *mut c_void);
+    fn dpu_cni_transfer_matrix_add_dpu(link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar, buffer: *mut c_uchar, length: c_uint, offset: c_uint, mram_number: c_uint);
+    fn dpu_cni_transfer_matrix_clear_dpu(link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar);
+    fn dpu_cni_transfer_matrix_clear_all(link: *const c_void, matrix: *mut c_void);
     fn dpu_cni_copy_to_mram_number_for_dpus(link: *const c_void, matrix: *const c_void) -> CniStatus;
     fn dpu_cni_copy_from_mram_number_for_dpus(link: *const c_void, matrix: *const c_void) -> CniStatus;
     fn dpu_cni_copy_to_mram_number_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, to: c_uint, source: *const c_uchar, length: c_uint, mram_number: c_uint) -> CniStatus;
@@ -198,22 +306,17 @@ extern {
     fn dpu_cni_finalize_fault_process_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus;
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 #[repr(C)]
 pub enum DpuType {
     FunctionalSimulator = 0,
     CycleAccurateSimulator = 1,
     Modelsim = 2,
+    #[default]
     Hardware = 3,
     BackupSpi = 4
 }
 
-impl Default for DpuType {
-    fn default() -> Self {
-        DpuType::Hardware
-    }
-}
-
 #[derive(Clone, Default, Debug)]
 pub struct DpuProfile {
     properties: HashMap<String, String>
@@ -259,7 +362,10 @@ impl DpuTarget {
 }
 
 #[derive(Debug, Hash, Eq, PartialEq)]
-pub struct DpuRank(*const c_void);
+pub struct DpuRank {
+    link: *const c_void,
+    topology: DpuTopology
+}
 
 #[derive(Debug)]
 pub struct DpuRankTransferMatrix<'a> {
@@ -267,6 +373,19 @@ pub struct DpuRankTransferMatrix<'a> {
     rank: &'a DpuRank
 }
 
+const MAX_NR_OF_SIMULATED_RANKS: usize = 8;
+
+// the driver exposes one device per hardware rank
+#[cfg(not(feature = "mock"))]
+fn nr_of_hardware_ranks() -> usize {
+    std::fs::read_dir("/dev")
+        .map(|entries| entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("dpu_rank"))
+            .count())
+        .unwrap_or(0)
+}
+
 fn wrap_cni_status(status: CniStatus) -> Result<(), DpuError> {
     match status {
         CniStatus::Success => Ok(()),
@@ -274,31 +393,55 @@ fn wrap_cni_status(status: CniStatus) -> Result<(), DpuError> {
     }
 }
 
+// raw pointers are forwarded as is to the CNI, the caller is responsible for the buffer sizes
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 impl DpuRank {
-    pub fn find_nr_of_available_dpus_for(dpu_type: DpuType, _profile: &str) -> Result<u32, DpuError> {
-        // todo
+    pub fn find_nr_of_available_dpus_for(dpu_type: DpuType, profile: &str) -> Result<u32, DpuError> {
+        let descriptions = DpuRank::find_available_ranks_for(dpu_type, profile, None)?;
+
+        Ok(descriptions.iter().map(|description| description.topology.nr_of_dpus()).sum())
+    }
+
+    // the ranks of the machine, in use or not
+    pub fn nr_of_ranks_for(dpu_type: DpuType) -> usize {
+        match dpu_type {
+            DpuType::Hardware | DpuType::BackupSpi => nr_of_hardware_ranks(),
+            _ => MAX_NR_OF_SIMULATED_RANKS,
+        }
+    }
 
-        let nr_of_dpus = match dpu_type {
-            DpuType::FunctionalSimulator => 8,
-            DpuType::Hardware => 0,
-            _ => 0
+    // Ranks are found by allocating as many of them as possible, then released: the other processes cannot
+    // allocate them meanwhile, so the probe stops at the ranks needed by the caller, if it knows how many.
+    // Simulated ranks can be allocated indefinitely, so their number is bounded.
+    pub fn find_available_ranks_for(dpu_type: DpuType, profile: &str, nr_of_ranks_needed: Option<usize>) -> Result<Vec<DpuRankDescription>, DpuError> {
+        let max_nr_of_ranks = match dpu_type {
+            DpuType::Hardware | DpuType::BackupSpi => nr_of_ranks_needed,
+            _ => Some(nr_of_ranks_needed.map_or(MAX_NR_OF_SIMULATED_RANKS, |nr| nr.min(MAX_NR_OF_SIMULATED_RANKS))),
         };
 
-        Ok(nr_of_dpus)
+        let mut ranks = Vec::default();
+
+        while max_nr_of_ranks.is_none_or(|max| ranks.len() < max) {
+            match DpuRank::allocate_for(dpu_type, profile) {
+                Ok(rank) => ranks.push(rank),
+                Err(DpuError(CniStatus::AllocationError)) => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        ranks.iter().map(|rank| rank.get_description()).collect()
     }
     
     pub fn get_description_for(dpu_type: DpuType, profile: &str) -> Result<DpuRankDescription, DpuError> {
         // unwrap: CString::new cannot return an error with a Rust String as argument
         let c_profile = CString::new(profile).unwrap();
 
-        let mut description;
+        let mut description = MaybeUninit::uninit();
 
-        let status = unsafe {
-            description = std::mem::uninitialized();
-            dpu_cni_get_profile_description(dpu_type, c_profile.as_ptr(), &mut description)
-        };
+        let status = unsafe { dpu_cni_get_profile_description(dpu_type, c_profile.as_ptr(), description.as_mut_ptr()) };
 
-        wrap_cni_status(status).map(|_| description)
+        // safety: the description is filled by the CNI when the call succeeds
+        wrap_cni_status(status).map(|_| unsafe { description.assume_init() })
     }
 
     pub fn allocate_for(dpu_type: DpuType, profile: &str) -> Result<DpuRank, DpuError> {
@@ -308,199 +451,213 @@ impl DpuRank {
 
         let status = unsafe { dpu_cni_get_rank_of_type(dpu_type, c_profile.as_ptr(), &mut link) };
 
-        wrap_cni_status(status).map(|_| DpuRank(link))
+        wrap_cni_status(status)?;
+
+        let mut rank = DpuRank { link, topology: DpuTopology::default() };
+        rank.topology = rank.get_description()?.topology;
+
+        Ok(rank)
     }
 
     pub fn get_description(&self) -> Result<DpuRankDescription, DpuError> {
-        let mut description;
+        let mut description = MaybeUninit::uninit();
 
-        let status = unsafe {
-            description = std::mem::uninitialized();
-            dpu_cni_get_target_description(self.0, &mut description)
-        };
+        let status = unsafe { dpu_cni_get_target_description(self.link, description.as_mut_ptr()) };
 
-        wrap_cni_status(status).map(|_| description)
+        // safety: the description is filled by the CNI when the call succeeds
+        wrap_cni_status(status).map(|_| unsafe { description.assume_init() })
     }
 
     pub fn reset_all(&self) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_reset_for_all(self.0) };
+        let status = unsafe { dpu_cni_reset_for_all(self.link) };
 
         wrap_cni_status(status)
     }
 
     pub fn reset_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_reset_for_dpu(self.0, slice_id, member_id) };
+        let status = unsafe { dpu_cni_reset_for_dpu(self.link, slice_id, member_id) };
 
         wrap_cni_status(status)
     }
 
-    pub fn launch_thread_on_all(&self, thread: u8, should_resume: bool, was_running: *mut u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_launch_thread_for_all(self.0, thread, should_resume, was_running) };
+    pub fn launch_thread_on_all(&self, thread: u8, should_resume: bool) -> Result<RankBitmap, DpuError> {
+        let mut was_running = RankBitmap::for_topology(&self.topology);
+        let status = unsafe { dpu_cni_launch_thread_for_all(self.link, thread, should_resume, was_running.slices.as_mut_ptr()) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| was_running)
     }
 
-    pub fn launch_thread_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, should_resume: bool, was_running: *mut bool) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_launch_thread_for_dpu(self.0, slice_id, member_id, thread, should_resume, was_running) };
+    pub fn launch_thread_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, should_resume: bool) -> Result<bool, DpuError> {
+        let mut was_running = false;
+        let status = unsafe { dpu_cni_launch_thread_for_dpu(self.link, slice_id, member_id, thread, should_resume, &mut was_running) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| was_running)
     }
 
-    pub fn poll_all(&self, is_running: *mut u32, is_in_fault: *mut u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_poll_for_all(self.0, is_running, is_in_fault) };
+    pub fn poll_all(&self) -> Result<(RankBitmap, RankBitmap), DpuError> {
+        let mut is_running = RankBitmap::for_topology(&self.topology);
+        let mut is_in_fault = RankBitmap::for_topology(&self.topology);
+        let status = unsafe { dpu_cni_poll_for_all(self.link, is_running.slices.as_mut_ptr(), is_in_fault.slices.as_mut_ptr()) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| (is_running, is_in_fault))
     }
 
-    pub fn poll_dpu(&self, slice_id: u8, member_id: u8, is_running: *mut bool, is_in_fault: *mut bool) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_poll_for_dpu(self.0, slice_id, member_id, is_running, is_in_fault) };
+    pub fn poll_dpu(&self, slice_id: u8, member_id: u8) -> Result<(bool, bool), DpuError> {
+        let mut is_running = false;
+        let mut is_in_fault = false;
+        let status = unsafe { dpu_cni_poll_for_dpu(self.link, slice_id, member_id, &mut is_running, &mut is_in_fault) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| (is_running, is_in_fault))
     }
 
-    pub fn fetch_thread_status_on_all(&self, thread: u8, is_running: *mut u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_get_thread_status_for_all(self.0, thread, is_running) };
+    pub fn fetch_thread_status_on_all(&self, thread: u8) -> Result<RankBitmap, DpuError> {
+        let mut is_running = RankBitmap::for_topology(&self.topology);
+        let status = unsafe { dpu_cni_get_thread_status_for_all(self.link, thread, is_running.slices.as_mut_ptr()) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| is_running)
     }
 
-    pub fn fetch_thread_status_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, is_running: *mut bool) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_get_thread_status_for_dpu(self.0, slice_id, member_id, thread, is_running) };
+    pub fn fetch_thread_status_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8) -> Result<bool, DpuError> {
+        let mut is_running = false;
+        let status = unsafe { dpu_cni_get_thread_status_for_dpu(self.link, slice_id, member_id, thread, &mut is_running) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| is_running)
     }
 
-    pub fn get_and_update_notification_on_all(&self, notify: u8, update: bool, was_set: *mut u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_get_and_update_notify_status_for_all(self.0, notify, update, was_set) };
+    pub fn get_and_update_notification_on_all(&self, notify: u8, update: bool) -> Result<RankBitmap, DpuError> {
+        let mut was_set = RankBitmap::for_topology(&self.topology);
+        let status = unsafe { dpu_cni_get_and_update_notify_status_for_all(self.link, notify, update, was_set.slices.as_mut_ptr()) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| was_set)
     }
 
-    pub fn get_and_update_notification_on_dpu(&self, slice_id: u8, member_id: u8, notify: u8, update: bool, was_set: *mut bool) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_get_and_update_notify_status_for_dpu(self.0, slice_id, member_id, notify, update, was_set) };
+    pub fn get_and_update_notification_on_dpu(&self, slice_id: u8, member_id: u8, notify: u8, update: bool) -> Result<bool, DpuError> {
+        let mut was_set = false;
+        let status = unsafe { dpu_cni_get_and_update_notify_status_for_dpu(self.link, slice_id, member_id, notify, update, &mut was_set) };
 
-        wrap_cni_status(status)
+        wrap_cni_status(status).map(|_| was_set)
     }
 
     pub fn fault_all(&self) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_trigger_fault_on_all(self.0) };
+        let status = unsafe { dpu_cni_trigger_fault_on_all(self.link) };
 
         wrap_cni_status(status)
     }
 
     pub fn fault_dpu(&self, slice_id: u8, member_id: u8) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_trigger_fault_on_dpu(self.0, slice_id, member_id) };
+        let status = unsafe { dpu_cni_trigger_fault_on_dpu(self.link, slice_id, member_id) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_irams(&self, buffer: *const u64, length: u16, offset: u16) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_iram_for_all(self.0, offset, buffer, length) };
+        let status = unsafe { dpu_cni_copy_to_iram_for_all(self.link, offset, buffer, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_iram(&self, slice_id: u8, member_id: u8, buffer: *const u64, length: u16, offset: u16) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_iram_for_dpu(self.0, slice_id, member_id, offset, buffer, length) };
+        let status = unsafe { dpu_cni_copy_to_iram_for_dpu(self.link, slice_id, member_id, offset, buffer, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_from_iram(&self, slice_id: u8, member_id: u8, buffer: *mut u64, length: u16, offset: u16) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_from_iram_for_dpu(self.0, slice_id, member_id, buffer, offset, length) };
+        let status = unsafe { dpu_cni_copy_from_iram_for_dpu(self.link, slice_id, member_id, buffer, offset, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_wrams(&self, buffer: *const u32, length: u32, offset: u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_wram_for_all(self.0, offset, buffer, length) };
+        let status = unsafe { dpu_cni_copy_to_wram_for_all(self.link, offset, buffer, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_wram(&self, slice_id: u8, member_id: u8, buffer: *const u32, length: u32, offset: u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_wram_for_dpu(self.0, slice_id, member_id, offset, buffer, length) };
+        let status = unsafe { dpu_cni_copy_to_wram_for_dpu(self.link, slice_id, member_id, offset, buffer, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_from_wram(&self, slice_id: u8, member_id: u8, buffer: *mut u32, length: u32, offset: u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_from_wram_for_dpu(self.0, slice_id, member_id, buffer, offset, length) };
+        let status = unsafe { dpu_cni_copy_from_wram_for_dpu(self.link, slice_id, member_id, buffer, offset, length) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_mrams(&self, matrix: &DpuRankTransferMatrix<'_>) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_mram_number_for_dpus(self.0, matrix.matrix) };
+        let status = unsafe { dpu_cni_copy_to_mram_number_for_dpus(self.link, matrix.matrix) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_to_mram(&self, slice_id: u8, member_id: u8, buffer: *const u8, length: u32, offset: u32, mram_number: u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_to_mram_number_for_dpu(self.0, slice_id, member_id, offset, buffer, length, mram_number) };
+        let status = unsafe { dpu_cni_copy_to_mram_number_for_dpu(self.link, slice_id, member_id, offset, buffer, length, mram_number) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_from_mrams(&self, matrix: &DpuRankTransferMatrix<'_>) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_from_mram_number_for_dpus(self.0, matrix.matrix) };
+        let status = unsafe { dpu_cni_copy_from_mram_number_for_dpus(self.link, matrix.matrix) };
 
         wrap_cni_status(status)
     }
 
     pub fn copy_from_mram(&self, slice_id: u8, member_id: u8, buffer: *mut u8, length: u32, offset: u32, mram_number: u32) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_copy_from_mram_number_for_dpu(self.0, slice_id, member_id, buffer, offset, length, mram_number) };
+        let status = unsafe { dpu_cni_copy_from_mram_number_for_dpu(self.link, slice_id, member_id, buffer, offset, length, mram_number) };
 
         wrap_cni_status(status)
     }
 
     pub fn extract_pcs_from_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_extract_pcs_for_dpu(self.0, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
+        let status = unsafe { dpu_cni_extract_pcs_for_dpu(self.link, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
 
         wrap_cni_status(status)
     }
 
     pub fn extract_context_from_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_extract_context_for_dpu(self.0, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
+        let status = unsafe { dpu_cni_extract_context_for_dpu(self.link, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
 
         wrap_cni_status(status)
     }
 
     pub fn initialize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_initialize_fault_process_for_dpu(self.0, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
+        let status = unsafe { dpu_cni_initialize_fault_process_for_dpu(self.link, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
 
         wrap_cni_status(status)
     }
 
     pub fn execute_thread_step_on_dpu(&self, slice_id: u8, member_id: u8, thread: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_execute_thread_step_in_fault_for_dpu(self.0, slice_id, member_id, thread, &mut context.raw as *mut RawDpuDebugContext) };
+        let status = unsafe { dpu_cni_execute_thread_step_in_fault_for_dpu(self.link, slice_id, member_id, thread, &mut context.raw as *mut RawDpuDebugContext) };
 
         wrap_cni_status(status)
     }
 
     pub fn finalize_fault_process_for_dpu(&self, slice_id: u8, member_id: u8, context: &mut DpuDebugContext) -> Result<(), DpuError> {
-        let status = unsafe { dpu_cni_finalize_fault_process_for_dpu(self.0, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
+        let status = unsafe { dpu_cni_finalize_fault_process_for_dpu(self.link, slice_id, member_id, &mut context.raw as *mut RawDpuDebugContext) };
 
         wrap_cni_status(status)
     }
 
-    pub fn free(self) -> () {
+    pub fn free(self) {
         // drop will do the job
     }
 }
 
 impl Drop for DpuRank {
     fn drop(&mut self) {
-        unsafe { dpu_cni_free_rank(self.0); }
+        unsafe { dpu_cni_free_rank(self.link); }
     }
 }
 
 unsafe impl Send for DpuRank {}
 unsafe impl Sync for DpuRank {}
 
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 impl <'a> DpuRankTransferMatrix<'a> {
     pub fn allocate_for(rank: &'a DpuRank) -> Result<DpuRankTransferMatrix<'a>, DpuError> {
-        let DpuRank(link) = *rank;
+        let link = rank.link;
         let mut matrix = std::ptr::null_mut();
 
         let status = unsafe { dpu_cni_transfer_matrix_allocate(link, &mut matrix) };
@@ -508,26 +665,26 @@ impl <'a> DpuRankTransferMatrix<'a> {
         wrap_cni_status(status).map(|_| DpuRankTransferMatrix { matrix, rank })
     }
 
-    pub fn add_dpu(&self, slice_id: u8, member_id: u8, buffer: *mut u8, length: u32, offset: u32, mram_number: u32) -> () {
-        unsafe { dpu_cni_transfer_matrix_add_dpu(self.rank.0, self.matrix, slice_id, member_id, buffer, length, offset, mram_number) }
+    pub fn add_dpu(&self, slice_id: u8, member_id: u8, buffer: *mut u8, length: u32, offset: u32, mram_number: u32) {
+        unsafe { dpu_cni_transfer_matrix_add_dpu(self.rank.link, self.matrix, slice_id, member_id, buffer, length, offset, mram_number) }
     }
 
-    pub fn clear_dpu(&self, slice_id: u8, member_id: u8) -> () {
-        unsafe { dpu_cni_transfer_matrix_clear_dpu(self.rank.0, self.matrix, slice_id, member_id) }
+    pub fn clear_dpu(&self, slice_id: u8, member_id: u8) {
+        unsafe { dpu_cni_transfer_matrix_clear_dpu(self.rank.link, self.matrix, slice_id, member_id) }
     }
 
-    pub fn clear_all(&self) -> () {
-        unsafe { dpu_cni_transfer_matrix_clear_all(self.rank.0, self.matrix) }
+    pub fn clear_all(&self) {
+        unsafe { dpu_cni_transfer_matrix_clear_all(self.rank.link, self.matrix) }
     }
 
-    pub fn free(self) -> () {
+    pub fn free(self) {
         // drop will do the job
     }
 }
 
 impl <'a> Drop for DpuRankTransferMatrix<'a> {
     fn drop(&mut self) {
-        unsafe { dpu_cni_transfer_matrix_free(self.rank.0, self.matrix); }
+        unsafe { dpu_cni_transfer_matrix_free(self.rank.link, self.matrix); }
     }
 }
 
@@ -0,0 +1,727 @@
+//! In-process implementation of the CNI entry points, enabled by the `mock` feature.
+//!
+//! Every rank keeps its IRAM, WRAM and MRAM in host memory, so that the upper layers can be
+//! exercised without the UPMEM SDK. No DPU code is interpreted: a launched thread is reported as
+//! running until the next poll of its DPU, then stops. A faulted DPU stays running and in fault
+//! until its fault process is finalized or it is reset. The machine has a fixed number of hardware
+//! ranks, while simulated ranks can be allocated without limit.
+
+// signatures mirror the CNI ones
+#![allow(clippy::too_many_arguments)]
+
+use libc::{c_void, c_uchar, c_char, c_uint, c_ushort, c_ulong};
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use crate::{CniStatus, DpuType, DpuRankDescription, DpuSignature, DpuStaticConfiguration, DpuTopology,
+            DpuMemories, DpuMemoryRepair, DpuInfo, RawDpuDebugContext};
+
+const NR_OF_THREADS: u8 = 24;
+const NR_OF_WORK_REGISTERS_PER_THREAD: u8 = 24;
+const NR_OF_ATOMIC_BITS: u32 = 256;
+const NR_OF_NOTIFY_BITS: u32 = 40;
+const IRAM_SIZE: u16 = 4096;
+const WRAM_SIZE: u32 = 16 * 1024;
+const MRAM_SIZE: u32 = 64 * 1024 * 1024;
+const INSTRUCTION_MASK: u64 = 0x0000_FFFF_FFFF_FFFF;
+const NR_OF_HARDWARE_RANKS: usize = 4;
+
+// hardware ranks are a limited resource, simulated ones are not
+static NR_OF_ALLOCATED_HARDWARE_RANKS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy)]
+struct MockTopology {
+    nr_of_control_interfaces: u8,
+    nr_of_dpus_per_control_interface: u8
+}
+
+struct MockFault {
+    thread: u8
+}
+
+struct MockDpu {
+    iram: Vec<u64>,
+    wram: Vec<u32>,
+    // grown on demand, bytes after the end read as zeros
+    mram: Vec<u8>,
+    threads: Vec<bool>,
+    pcs: Vec<u16>,
+    registers: Vec<u32>,
+    atomic_register: Vec<bool>,
+    zero_flags: Vec<bool>,
+    carry_flags: Vec<bool>,
+    notify: Vec<bool>,
+    fault: Option<MockFault>
+}
+
+struct MockRank {
+    dpu_type: DpuType,
+    topology: MockTopology,
+    dpus: Mutex<Vec<MockDpu>>
+}
+
+struct MockMatrixEntry {
+    slice_id: u8,
+    member_id: u8,
+    buffer: *mut u8,
+    length: u32,
+    offset: u32,
+    mram_number: u32
+}
+
+struct MockTransferMatrix(Vec<MockMatrixEntry>);
+
+impl MockDpu {
+    fn new() -> Self {
+        MockDpu {
+            iram: vec![0; IRAM_SIZE as usize],
+            wram: vec![0; WRAM_SIZE as usize],
+            mram: Vec::default(),
+            threads: vec![false; NR_OF_THREADS as usize],
+            pcs: vec![0; NR_OF_THREADS as usize],
+            registers: vec![0; (NR_OF_THREADS as usize) * (NR_OF_WORK_REGISTERS_PER_THREAD as usize)],
+            atomic_register: vec![false; NR_OF_ATOMIC_BITS as usize],
+            zero_flags: vec![false; NR_OF_THREADS as usize],
+            carry_flags: vec![false; NR_OF_THREADS as usize],
+            notify: vec![false; NR_OF_NOTIFY_BITS as usize],
+            fault: None
+        }
+    }
+
+    fn reset(&mut self) {
+        let mram = std::mem::take(&mut self.mram);
+        let iram = std::mem::take(&mut self.iram);
+        let wram = std::mem::take(&mut self.wram);
+
+        *self = MockDpu { iram, wram, mram, ..MockDpu::new() };
+    }
+
+    fn is_running(&self) -> bool {
+        self.threads.iter().any(|running| *running)
+    }
+
+    fn poll(&mut self) -> (bool, bool) {
+        let is_running = self.is_running();
+        let is_in_fault = self.fault.is_some();
+
+        if !is_in_fault {
+            for running in self.threads.iter_mut() {
+                *running = false;
+            }
+        }
+
+        (is_running, is_in_fault)
+    }
+
+    fn launch(&mut self, thread: u8, should_resume: bool) -> bool {
+        let was_running = self.threads[thread as usize];
+
+        if !should_resume {
+            self.pcs[thread as usize] = 0;
+        }
+        self.threads[thread as usize] = true;
+
+        was_running
+    }
+
+    fn mram_write(&mut self, offset: u32, data: &[u8]) {
+        let start = offset as usize;
+        let end = start + data.len();
+
+        if self.mram.len() < end {
+            self.mram.resize(end, 0);
+        }
+
+        self.mram[start..end].copy_from_slice(data);
+    }
+
+    fn mram_read(&self, offset: u32, data: &mut [u8]) {
+        for (idx, byte) in data.iter_mut().enumerate() {
+            *byte = self.mram.get(offset as usize + idx).copied().unwrap_or(0);
+        }
+    }
+
+    unsafe fn fill_context(&self, context: &mut RawDpuDebugContext, with_registers: bool) {
+        let nr_of_threads = self.pcs.len();
+
+        std::ptr::copy_nonoverlapping(self.pcs.as_ptr(), context.pcs, nr_of_threads);
+
+        if !with_registers {
+            return;
+        }
+
+        std::ptr::copy_nonoverlapping(self.registers.as_ptr(), context.registers, self.registers.len());
+        std::ptr::copy_nonoverlapping(self.atomic_register.as_ptr(), context.atomic_register, self.atomic_register.len());
+        std::ptr::copy_nonoverlapping(self.zero_flags.as_ptr(), context.zero_flags, nr_of_threads);
+        std::ptr::copy_nonoverlapping(self.carry_flags.as_ptr(), context.carry_flags, nr_of_threads);
+
+        let mut nr_of_running_threads = 0;
+        for (thread, running) in self.threads.iter().enumerate() {
+            let order = if *running {
+                nr_of_running_threads += 1;
+                nr_of_running_threads - 1
+            } else {
+                0xFF
+            };
+            *context.scheduling.add(thread) = order;
+        }
+        context.nr_of_running_threads = nr_of_running_threads;
+
+        context.bkp_fault = self.fault.is_some();
+        context.bkp_fault_thread_index = self.fault.as_ref().map(|fault| fault.thread).unwrap_or(0);
+        context.dma_fault = false;
+        context.dma_fault_thread_index = 0;
+        context.mem_fault = false;
+        context.mem_fault_thread_index = 0;
+    }
+
+    unsafe fn restore_context(&mut self, context: &RawDpuDebugContext) {
+        let nr_of_threads = self.pcs.len();
+
+        std::ptr::copy_nonoverlapping(context.pcs, self.pcs.as_mut_ptr(), nr_of_threads);
+        std::ptr::copy_nonoverlapping(context.registers, self.registers.as_mut_ptr(), self.registers.len());
+        std::ptr::copy_nonoverlapping(context.atomic_register, self.atomic_register.as_mut_ptr(), self.atomic_register.len());
+        std::ptr::copy_nonoverlapping(context.zero_flags, self.zero_flags.as_mut_ptr(), nr_of_threads);
+        std::ptr::copy_nonoverlapping(context.carry_flags, self.carry_flags.as_mut_ptr(), nr_of_threads);
+    }
+}
+
+impl MockRank {
+    fn with_dpu<F>(&self, slice_id: u8, member_id: u8, f: F) -> CniStatus
+        where F: FnOnce(&mut MockDpu) -> CniStatus
+    {
+        if slice_id >= self.topology.nr_of_control_interfaces {
+            return CniStatus::InvalidSliceIdError;
+        }
+        if member_id >= self.topology.nr_of_dpus_per_control_interface {
+            return CniStatus::InvalidMemberIdError;
+        }
+
+        let index = (slice_id as usize) * (self.topology.nr_of_dpus_per_control_interface as usize) + (member_id as usize);
+        let mut dpus = self.dpus.lock().unwrap();
+
+        f(&mut dpus[index])
+    }
+
+    unsafe fn for_all_bitfields<F>(&self, bitfield: *mut c_uint, mut f: F) -> CniStatus
+        where F: FnMut(&mut MockDpu) -> bool
+    {
+        let nr_of_dpus_per_control_interface = self.topology.nr_of_dpus_per_control_interface as usize;
+        let mut dpus = self.dpus.lock().unwrap();
+
+        for slice_id in 0..(self.topology.nr_of_control_interfaces as usize) {
+            let mut slice_bitfield = 0;
+
+            for member_id in 0..nr_of_dpus_per_control_interface {
+                if f(&mut dpus[slice_id * nr_of_dpus_per_control_interface + member_id]) {
+                    slice_bitfield |= 1 << member_id;
+                }
+            }
+
+            *bitfield.add(slice_id) = slice_bitfield;
+        }
+
+        CniStatus::Success
+    }
+}
+
+fn parse_profile(dpu_type: DpuType, profile: *const c_char) -> Result<MockTopology, CniStatus> {
+    let mut topology = if is_hardware(dpu_type) {
+        MockTopology { nr_of_control_interfaces: 8, nr_of_dpus_per_control_interface: 8 }
+    } else {
+        MockTopology { nr_of_control_interfaces: 1, nr_of_dpus_per_control_interface: 1 }
+    };
+
+    let profile = unsafe { CStr::from_ptr(profile) }.to_str().map_err(|_| CniStatus::InvalidProfileError)?;
+    let properties = profile.split('&')
+        .filter(|property| !property.is_empty())
+        .map(|property| {
+            let mut parts = property.splitn(2, '=');
+            (parts.next().unwrap_or(""), parts.next().unwrap_or(""))
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (key, value) in properties {
+        match key {
+            // same constraint as the functional simulator
+            "nrDpusPerCI" => match value.parse() {
+                Ok(nr @ 1) | Ok(nr @ 8) => topology.nr_of_dpus_per_control_interface = nr,
+                _ => return Err(CniStatus::InvalidProfileError),
+            },
+            "nrCIs" => match value.parse() {
+                Ok(nr @ 1..=8) => topology.nr_of_control_interfaces = nr,
+                _ => return Err(CniStatus::InvalidProfileError),
+            },
+            _ => return Err(CniStatus::InvalidProfileError),
+        }
+    }
+
+    Ok(topology)
+}
+
+fn description_for(dpu_type: DpuType, topology: MockTopology) -> DpuRankDescription {
+    DpuRankDescription {
+        signature: DpuSignature { config_id: 0, chip_id: 0 },
+        static_config: DpuStaticConfiguration {
+            cmd_duration: 0,
+            cmd_sampling: 0,
+            res_duration: 0,
+            res_sampling: 0,
+            reset_wait_duration: 0,
+            std_temperature: 0,
+            clock_division: 0
+        },
+        topology: DpuTopology {
+            nr_of_control_interfaces: topology.nr_of_control_interfaces,
+            nr_of_dpus_per_control_interface: topology.nr_of_dpus_per_control_interface
+        },
+        memories: DpuMemories {
+            mram_size: MRAM_SIZE,
+            wram_size: WRAM_SIZE,
+            iram_size: IRAM_SIZE,
+            dbg_mram_size: 0,
+            repair: DpuMemoryRepair {
+                do_iram_repair: false,
+                do_wram_repair: false,
+                iram_repair: std::ptr::null_mut(),
+                wram_repair: std::ptr::null_mut()
+            },
+            cycle_accurate: !matches!(dpu_type, DpuType::FunctionalSimulator)
+        },
+        info: DpuInfo {
+            nr_of_threads: NR_OF_THREADS,
+            nr_of_atomic_bits: NR_OF_ATOMIC_BITS,
+            nr_of_notify_bits: NR_OF_NOTIFY_BITS,
+            nr_of_work_registers_per_thread: NR_OF_WORK_REGISTERS_PER_THREAD
+        },
+        _internals: std::ptr::null(),
+        _free_internals: std::ptr::null()
+    }
+}
+
+fn is_hardware(dpu_type: DpuType) -> bool {
+    matches!(dpu_type, DpuType::Hardware | DpuType::BackupSpi)
+}
+
+pub fn nr_of_hardware_ranks() -> usize {
+    NR_OF_HARDWARE_RANKS
+}
+
+fn reserve_hardware_rank() -> bool {
+    NR_OF_ALLOCATED_HARDWARE_RANKS.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |nr| {
+        if nr < NR_OF_HARDWARE_RANKS { Some(nr + 1) } else { None }
+    }).is_ok()
+}
+
+fn rank<'a>(link: *const c_void) -> &'a MockRank {
+    unsafe { &*(link as *const MockRank) }
+}
+
+fn check_thread(thread: c_uchar) -> Result<(), CniStatus> {
+    if thread < NR_OF_THREADS { Ok(()) } else { Err(CniStatus::InvalidThreadIdError) }
+}
+
+fn check_notify_bit(notify_bit: c_uchar) -> Result<(), CniStatus> {
+    if (notify_bit as u32) < NR_OF_NOTIFY_BITS { Ok(()) } else { Err(CniStatus::InvalidNotifyIdError) }
+}
+
+fn check_iram_access(offset: c_ushort, length: c_ushort) -> Result<(), CniStatus> {
+    if (offset as u32) + (length as u32) <= (IRAM_SIZE as u32) { Ok(()) } else { Err(CniStatus::InvalidIramAccessError) }
+}
+
+fn check_wram_access(offset: c_uint, length: c_uint) -> Result<(), CniStatus> {
+    if (offset as u64) + (length as u64) <= (WRAM_SIZE as u64) { Ok(()) } else { Err(CniStatus::InvalidWramAccessError) }
+}
+
+fn check_mram_access(offset: c_uint, length: c_uint, mram_number: c_uint) -> Result<(), CniStatus> {
+    if mram_number == 0 && (offset as u64) + (length as u64) <= (MRAM_SIZE as u64) { Ok(()) } else { Err(CniStatus::InvalidMramAccessError) }
+}
+
+pub unsafe fn dpu_cni_get_profile_description(backend: DpuType, profile: *const c_char, description: *mut DpuRankDescription) -> CniStatus {
+    match parse_profile(backend, profile) {
+        Ok(topology) => {
+            description.write(description_for(backend, topology));
+            CniStatus::Success
+        },
+        Err(err) => err,
+    }
+}
+
+pub unsafe fn dpu_cni_get_rank_of_type(backend: DpuType, profile: *const c_char, link: *mut*const c_void) -> CniStatus {
+    match parse_profile(backend, profile) {
+        Ok(topology) => {
+            if is_hardware(backend) && !reserve_hardware_rank() {
+                return CniStatus::AllocationError;
+            }
+
+            let nr_of_dpus = (topology.nr_of_control_interfaces as usize) * (topology.nr_of_dpus_per_control_interface as usize);
+            let dpus = (0..nr_of_dpus).map(|_| MockDpu::new()).collect();
+            let rank = Box::new(MockRank { dpu_type: backend, topology, dpus: Mutex::new(dpus) });
+
+            *link = Box::into_raw(rank) as *const c_void;
+            CniStatus::Success
+        },
+        Err(err) => err,
+    }
+}
+
+pub unsafe fn dpu_cni_free_rank(link: *const c_void) -> CniStatus {
+    let rank = Box::from_raw(link as *mut MockRank);
+
+    if is_hardware(rank.dpu_type) {
+        NR_OF_ALLOCATED_HARDWARE_RANKS.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_get_target_description(link: *const c_void, description: *mut DpuRankDescription) -> CniStatus {
+    let rank = rank(link);
+    description.write(description_for(rank.dpu_type, rank.topology));
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_reset_for_all(link: *const c_void) -> CniStatus {
+    let rank = rank(link);
+    let mut dpus = rank.dpus.lock().unwrap();
+
+    for dpu in dpus.iter_mut() {
+        dpu.reset();
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_reset_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.reset();
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_launch_thread_for_all(link: *const c_void, thread: c_uchar, should_resume: bool, was_running: *mut c_uint) -> CniStatus {
+    if let Err(err) = check_thread(thread) {
+        return err;
+    }
+
+    rank(link).for_all_bitfields(was_running, |dpu| dpu.launch(thread, should_resume))
+}
+
+pub unsafe fn dpu_cni_launch_thread_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, thread: c_uchar, should_resume: bool, was_running: *mut bool) -> CniStatus {
+    if let Err(err) = check_thread(thread) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        *was_running = dpu.launch(thread, should_resume);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_poll_for_all(link: *const c_void, is_running: *mut c_uint, is_in_fault: *mut c_uint) -> CniStatus {
+    let rank = rank(link);
+    let nr_of_dpus_per_control_interface = rank.topology.nr_of_dpus_per_control_interface as usize;
+    let mut dpus = rank.dpus.lock().unwrap();
+
+    for slice_id in 0..(rank.topology.nr_of_control_interfaces as usize) {
+        let mut running_bitfield = 0;
+        let mut fault_bitfield = 0;
+
+        for member_id in 0..nr_of_dpus_per_control_interface {
+            let (running, fault) = dpus[slice_id * nr_of_dpus_per_control_interface + member_id].poll();
+
+            if running {
+                running_bitfield |= 1 << member_id;
+            }
+            if fault {
+                fault_bitfield |= 1 << member_id;
+            }
+        }
+
+        *is_running.add(slice_id) = running_bitfield;
+        *is_in_fault.add(slice_id) = fault_bitfield;
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_poll_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, is_running: *mut bool, is_in_fault: *mut bool) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        let (running, fault) = dpu.poll();
+        *is_running = running;
+        *is_in_fault = fault;
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_get_thread_status_for_all(link: *const c_void, thread: c_uchar, is_running: *mut c_uint) -> CniStatus {
+    if let Err(err) = check_thread(thread) {
+        return err;
+    }
+
+    rank(link).for_all_bitfields(is_running, |dpu| dpu.threads[thread as usize])
+}
+
+pub unsafe fn dpu_cni_get_thread_status_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, thread: c_uchar, is_running: *mut bool) -> CniStatus {
+    if let Err(err) = check_thread(thread) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        *is_running = dpu.threads[thread as usize];
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_get_and_update_notify_status_for_all(link: *const c_void, notify_bit: c_uchar, value: bool, was_set: *mut c_uint) -> CniStatus {
+    if let Err(err) = check_notify_bit(notify_bit) {
+        return err;
+    }
+
+    rank(link).for_all_bitfields(was_set, |dpu| std::mem::replace(&mut dpu.notify[notify_bit as usize], value))
+}
+
+pub unsafe fn dpu_cni_get_and_update_notify_status_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, notify_bit: c_uchar, value: bool, was_set: *mut bool) -> CniStatus {
+    if let Err(err) = check_notify_bit(notify_bit) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        *was_set = std::mem::replace(&mut dpu.notify[notify_bit as usize], value);
+        CniStatus::Success
+    })
+}
+
+fn trigger_fault(dpu: &mut MockDpu) {
+    let thread = dpu.threads.iter().position(|running| *running).unwrap_or(0) as u8;
+    dpu.threads[thread as usize] = true;
+    dpu.fault = Some(MockFault { thread });
+}
+
+pub unsafe fn dpu_cni_trigger_fault_on_all(link: *const c_void) -> CniStatus {
+    let rank = rank(link);
+    let mut dpus = rank.dpus.lock().unwrap();
+
+    for dpu in dpus.iter_mut() {
+        trigger_fault(dpu);
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_trigger_fault_on_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        trigger_fault(dpu);
+        CniStatus::Success
+    })
+}
+
+unsafe fn copy_to_iram(dpu: &mut MockDpu, to: c_ushort, source: *const c_ulong, length: c_ushort) {
+    let source = std::slice::from_raw_parts(source, length as usize);
+
+    for (instruction, value) in dpu.iram[(to as usize)..].iter_mut().zip(source) {
+        *instruction = *value & INSTRUCTION_MASK;
+    }
+}
+
+pub unsafe fn dpu_cni_copy_to_iram_for_all(link: *const c_void, to: c_ushort, source: *const c_ulong, length: c_ushort) -> CniStatus {
+    if let Err(err) = check_iram_access(to, length) {
+        return err;
+    }
+
+    let rank = rank(link);
+    let mut dpus = rank.dpus.lock().unwrap();
+
+    for dpu in dpus.iter_mut() {
+        copy_to_iram(dpu, to, source, length);
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_copy_to_iram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, to: c_ushort, source: *const c_ulong, length: c_ushort) -> CniStatus {
+    if let Err(err) = check_iram_access(to, length) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        copy_to_iram(dpu, to, source, length);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_copy_from_iram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, destination: *mut c_ulong, from: c_ushort, length: c_ushort) -> CniStatus {
+    if let Err(err) = check_iram_access(from, length) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        let iram = &dpu.iram[(from as usize)..((from as usize) + (length as usize))];
+        std::ptr::copy_nonoverlapping(iram.as_ptr(), destination, iram.len());
+        CniStatus::Success
+    })
+}
+
+unsafe fn copy_to_wram(dpu: &mut MockDpu, to: c_uint, source: *const c_uint, length: c_uint) {
+    std::ptr::copy_nonoverlapping(source, dpu.wram[(to as usize)..].as_mut_ptr(), length as usize);
+}
+
+pub unsafe fn dpu_cni_copy_to_wram_for_all(link: *const c_void, to: c_uint, source: *const c_uint, length: c_uint) -> CniStatus {
+    if let Err(err) = check_wram_access(to, length) {
+        return err;
+    }
+
+    let rank = rank(link);
+    let mut dpus = rank.dpus.lock().unwrap();
+
+    for dpu in dpus.iter_mut() {
+        copy_to_wram(dpu, to, source, length);
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_copy_to_wram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, to: c_uint, source: *const c_uint, length: c_uint) -> CniStatus {
+    if let Err(err) = check_wram_access(to, length) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        copy_to_wram(dpu, to, source, length);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_copy_from_wram_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, destination: *mut c_uint, from: c_uint, length: c_uint) -> CniStatus {
+    if let Err(err) = check_wram_access(from, length) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        std::ptr::copy_nonoverlapping(dpu.wram[(from as usize)..].as_ptr(), destination, length as usize);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_transfer_matrix_allocate(_link: *const c_void, matrix: *mut*mut c_void) -> CniStatus {
+    *matrix = Box::into_raw(Box::new(MockTransferMatrix(Vec::default()))) as *mut c_void;
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_transfer_matrix_free(_link: *const c_void, matrix: *mut c_void) {
+    drop(Box::from_raw(matrix as *mut MockTransferMatrix));
+}
+
+pub unsafe fn dpu_cni_transfer_matrix_add_dpu(link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar, buffer: *mut c_uchar, length: c_uint, offset: c_uint, mram_number: c_uint) {
+    dpu_cni_transfer_matrix_clear_dpu(link, matrix, slice_id, member_id);
+
+    let MockTransferMatrix(entries) = &mut *(matrix as *mut MockTransferMatrix);
+    entries.push(MockMatrixEntry { slice_id, member_id, buffer, length, offset, mram_number });
+}
+
+pub unsafe fn dpu_cni_transfer_matrix_clear_dpu(_link: *const c_void, matrix: *mut c_void, slice_id: c_uchar, member_id: c_uchar) {
+    let MockTransferMatrix(entries) = &mut *(matrix as *mut MockTransferMatrix);
+    entries.retain(|entry| entry.slice_id != slice_id || entry.member_id != member_id);
+}
+
+pub unsafe fn dpu_cni_transfer_matrix_clear_all(_link: *const c_void, matrix: *mut c_void) {
+    let MockTransferMatrix(entries) = &mut *(matrix as *mut MockTransferMatrix);
+    entries.clear();
+}
+
+pub unsafe fn dpu_cni_copy_to_mram_number_for_dpus(link: *const c_void, matrix: *const c_void) -> CniStatus {
+    let MockTransferMatrix(entries) = &*(matrix as *const MockTransferMatrix);
+
+    for entry in entries {
+        let status = dpu_cni_copy_to_mram_number_for_dpu(link, entry.slice_id, entry.member_id, entry.offset, entry.buffer, entry.length, entry.mram_number);
+
+        match status {
+            CniStatus::Success => (),
+            err => return err,
+        }
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_copy_from_mram_number_for_dpus(link: *const c_void, matrix: *const c_void) -> CniStatus {
+    let MockTransferMatrix(entries) = &*(matrix as *const MockTransferMatrix);
+
+    for entry in entries {
+        let status = dpu_cni_copy_from_mram_number_for_dpu(link, entry.slice_id, entry.member_id, entry.buffer, entry.offset, entry.length, entry.mram_number);
+
+        match status {
+            CniStatus::Success => (),
+            err => return err,
+        }
+    }
+
+    CniStatus::Success
+}
+
+pub unsafe fn dpu_cni_copy_to_mram_number_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, to: c_uint, source: *const c_uchar, length: c_uint, mram_number: c_uint) -> CniStatus {
+    if let Err(err) = check_mram_access(to, length, mram_number) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.mram_write(to, std::slice::from_raw_parts(source, length as usize));
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_copy_from_mram_number_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, destination: *mut c_uchar, from: c_uint, length: c_uint, mram_number: c_uint) -> CniStatus {
+    if let Err(err) = check_mram_access(from, length, mram_number) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.mram_read(from, std::slice::from_raw_parts_mut(destination, length as usize));
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_extract_pcs_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.fill_context(&mut *context, false);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_extract_context_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.fill_context(&mut *context, true);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_initialize_fault_process_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus {
+    dpu_cni_extract_context_for_dpu(link, slice_id, member_id, context)
+}
+
+pub unsafe fn dpu_cni_execute_thread_step_in_fault_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, thread: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus {
+    if let Err(err) = check_thread(thread) {
+        return err;
+    }
+
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        if !dpu.threads[thread as usize] {
+            return CniStatus::InvalidThreadIdError;
+        }
+
+        dpu.restore_context(&*context);
+        dpu.pcs[thread as usize] = (dpu.pcs[thread as usize] + 1) % IRAM_SIZE;
+        dpu.fill_context(&mut *context, true);
+        CniStatus::Success
+    })
+}
+
+pub unsafe fn dpu_cni_finalize_fault_process_for_dpu(link: *const c_void, slice_id: c_uchar, member_id: c_uchar, context: *mut RawDpuDebugContext) -> CniStatus {
+    rank(link).with_dpu(slice_id, member_id, |dpu| {
+        dpu.restore_context(&*context);
+        dpu.fault = None;
+        CniStatus::Success
+    })
+}
@@ -1,7 +1,5 @@
 use dpu_sys::DpuRank;
-use dpu_sys::DpuType;
 use dpu_sys::DpuTarget;
-use dpu_sys::DpuError;
 
 #[test]
 fn can_allocate_functional_simulator() {
@@ -25,7 +23,7 @@ fn can_allocate_functional_simulator_with_profile() {
 fn cannot_allocate_functional_simulator_with_invalid_profile() {
     let target = DpuTarget::for_functional_simulator().nr_of_dpus_per_control_interface(2);
     let (dpu_type, ref profile) = target.to_cni_args();
-    if let Ok(_) =  DpuRank::allocate_for(dpu_type, profile) {
+    if DpuRank::allocate_for(dpu_type, profile).is_ok() {
         panic!("should not be able to allocate rank with nr_of_dpus_per_control_interface = 2")
     }
 }
@@ -85,4 +83,19 @@ fn can_get_allocated_functional_simulator_description_with_modified_profile() {
         },
         Err(err) => panic!("{:?}", err),
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn can_find_available_functional_simulator_dpus() {
+    let target = DpuTarget::for_functional_simulator().nr_of_dpus_per_control_interface(8);
+    let (dpu_type, ref profile) = target.to_cni_args();
+    match DpuRank::find_available_ranks_for(dpu_type, profile, None) {
+        Ok(descriptions) => {
+            assert!(!descriptions.is_empty());
+            for description in descriptions {
+                assert_eq!(8, description.topology.nr_of_dpus());
+            }
+        },
+        Err(err) => panic!("{:?}", err),
+    }
+}
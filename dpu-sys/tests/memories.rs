@@ -16,7 +16,7 @@ fn allocate_rank() -> Result<DpuRank, DpuError> {
 fn can_access_irams() -> Result<(), DpuError> {
     let rank = allocate_rank()?;
     let description = rank.get_description()?;
-    let input = vec![0x0ABCDEF012345678, 0xFFFFFFFFFFFFFFFF, 0xAAAAAAAAAAAAAAAA];
+    let input = [0x0ABCDEF012345678, 0xFFFFFFFFFFFFFFFF, 0xAAAAAAAAAAAAAAAA];
 
     rank.copy_to_irams(input.as_ptr(), input.len() as u16, 10)?;
 
@@ -37,9 +37,9 @@ fn can_access_irams() -> Result<(), DpuError> {
 fn can_handle_iram_error() -> Result<(), DpuError> {
     let rank = allocate_rank()?;
     let description = rank.get_description()?;
-    let input = vec![0x0ABCDEF012345678, 0xFFFFFFFFFFFFFFFF, 0xAAAAAAAAAAAAAAAA];
+    let input = [0x0ABCDEF012345678, 0xFFFFFFFFFFFFFFFF, 0xAAAAAAAAAAAAAAAA];
 
-    if let Ok(_) = rank.copy_to_irams(input.as_ptr(), input.len() as u16, description.memories.iram_size) {
+    if rank.copy_to_irams(input.as_ptr(), input.len() as u16, description.memories.iram_size).is_ok() {
         panic!("should not be able to copy to irams after the end of the memory")
     }
 
@@ -50,7 +50,7 @@ fn can_handle_iram_error() -> Result<(), DpuError> {
 fn can_access_wrams() -> Result<(), DpuError> {
     let rank = allocate_rank()?;
     let description = rank.get_description()?;
-    let input = vec![0x12345678, 0xFFFFFFFF, 0xAAAAAAAA];
+    let input = [0x12345678, 0xFFFFFFFF, 0xAAAAAAAA];
 
     rank.copy_to_wrams(input.as_ptr(), input.len() as u32, 4)?;
 
@@ -71,9 +71,9 @@ fn can_access_wrams() -> Result<(), DpuError> {
 fn can_handle_wram_error() -> Result<(), DpuError> {
     let rank = allocate_rank()?;
     let description = rank.get_description()?;
-    let input = vec![0x0ABCDEF0, 0xFFFFFFFF, 0xAAAAAAAA];
+    let input = [0x0ABCDEF0, 0xFFFFFFFF, 0xAAAAAAAA];
 
-    if let Ok(_) = rank.copy_to_wrams(input.as_ptr(), input.len() as u32, description.memories.wram_size) {
+    if rank.copy_to_wrams(input.as_ptr(), input.len() as u32, description.memories.wram_size).is_ok() {
         panic!("should not be able to copy to wrams after the end of the memory")
     }
 
@@ -126,7 +126,7 @@ fn can_handle_mram_error() -> Result<(), DpuError> {
 
     matrix.add_dpu(0, 0, input.as_mut_ptr(), input.len() as u32, description.memories.mram_size, 0);
 
-    if let Ok(_) = rank.copy_to_mrams(&matrix) {
+    if rank.copy_to_mrams(&matrix).is_ok() {
         panic!("should not be able to copy to mrams after the end of the memory")
     }
 
@@ -0,0 +1,87 @@
+#![cfg(feature = "mock")]
+
+use dpu_sys::DpuError;
+use dpu_sys::DpuRank;
+use dpu_sys::DpuTarget;
+use dpu_sys::DpuDebugContext;
+
+fn allocate_rank() -> Result<DpuRank, DpuError> {
+    let target = DpuTarget::for_functional_simulator().nr_of_dpus_per_control_interface(8);
+    let (dpu_type, ref profile) = target.to_cni_args();
+    let rank = DpuRank::allocate_for(dpu_type, profile)?;
+    rank.reset_all()?;
+
+    Ok(rank)
+}
+
+#[test]
+fn launched_dpu_runs_until_polled() -> Result<(), DpuError> {
+    let rank = allocate_rank()?;
+
+    let was_running = rank.launch_thread_on_all(0, false)?;
+    assert!(was_running.is_empty());
+
+    let was_running = rank.launch_thread_on_all(0, false)?;
+    assert_eq!((0..8).map(|member| (0, member)).collect::<Vec<_>>(), was_running.iter().collect::<Vec<_>>());
+
+    let (is_running, is_in_fault) = rank.poll_all()?;
+    assert!(is_running.is_set(0, 7));
+    assert!(is_in_fault.is_empty());
+
+    let (is_running, _) = rank.poll_all()?;
+    assert!(is_running.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn can_update_notify_bits() -> Result<(), DpuError> {
+    let rank = allocate_rank()?;
+
+    assert!(!rank.get_and_update_notification_on_dpu(0, 3, 7, true)?);
+    assert!(rank.get_and_update_notification_on_dpu(0, 3, 7, true)?);
+
+    let was_set = rank.get_and_update_notification_on_all(7, true)?;
+    assert_eq!(vec![(0, 3)], was_set.iter().collect::<Vec<_>>());
+
+    Ok(())
+}
+
+#[test]
+fn faulted_dpu_stays_in_fault_until_finalized() -> Result<(), DpuError> {
+    let rank = allocate_rank()?;
+    let description = rank.get_description()?;
+    let mut context = DpuDebugContext::new(description.info.nr_of_threads,
+                                           description.info.nr_of_work_registers_per_thread,
+                                           description.info.nr_of_atomic_bits);
+
+    rank.fault_dpu(0, 5)?;
+
+    assert_eq!((true, true), rank.poll_dpu(0, 5)?);
+    assert_eq!((true, true), rank.poll_dpu(0, 5)?);
+
+    rank.initialize_fault_process_for_dpu(0, 5, &mut context)?;
+    assert_eq!(Some(0), context.breakpoint_fault());
+    assert_eq!(None, context.dma_fault());
+    assert_eq!(1, context.nr_of_running_threads());
+    rank.execute_thread_step_on_dpu(0, 5, 0, &mut context)?;
+    rank.finalize_fault_process_for_dpu(0, 5, &mut context)?;
+
+    let (_, is_in_fault) = rank.poll_dpu(0, 5)?;
+    assert!(!is_in_fault);
+
+    Ok(())
+}
+
+#[test]
+fn only_unused_hardware_ranks_are_available() -> Result<(), DpuError> {
+    let (dpu_type, ref profile) = DpuTarget::for_hardware_implementation().to_cni_args();
+    let nr_of_dpus = DpuRank::find_nr_of_available_dpus_for(dpu_type, profile)?;
+    assert_eq!(4 * 64, nr_of_dpus);
+
+    let _rank = DpuRank::allocate_for(dpu_type, profile)?;
+    assert_eq!(3, DpuRank::find_available_ranks_for(dpu_type, profile, None)?.len());
+    assert_eq!(2, DpuRank::find_available_ranks_for(dpu_type, profile, Some(2))?.len());
+
+    Ok(())
+}